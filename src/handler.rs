@@ -1,13 +1,10 @@
 use std::{
-    io::{BufRead, BufReader, Write},
-    net::{TcpListener, TcpStream}, collections::HashMap, path::PathBuf, error::Error, fmt::Debug, fs, sync::{Arc, Mutex},
+    io::{BufRead, BufReader},
+    net::{TcpListener, TcpStream}, error::Error, fmt::Debug, sync::{atomic::{AtomicUsize, Ordering}, Arc}, any::Any, time::Duration,
+    panic::{self, AssertUnwindSafe},
 };
 
-use crate::{http::{self, HTTPResponse, HTTPStatusCode, HTTPMethod, HTTPRequest}, thread_pool::{self, ThreadPool}, file::get_supported_filetypes};
-
-use self::listener::RequestListener;
-
-mod listener;
+use crate::{http::{self, Compression, HTTPResponse, HTTPMethod, HTTPRequest, HTTPStatusCode, HeaderMap, IntoResponse}, thread_pool::ThreadPool, mime::MimeRegistry, public_folder::PublicFolder, router::Router, state::State};
 
 /// # RequestHandler
 /// 
@@ -23,14 +20,22 @@ mod listener;
 /// 
 /// let mut handler = RequestHandler::new();
 /// handler.set_listener("GET", "/example", |request: HTTPRequest| {
-///   HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("Hello World!")))
+///   HTTPResponse::text("Hello World!")
 /// });
 /// handler.listen(8080).unwrap();
 /// ```
 pub struct RequestHandler {
-    listeners: HashMap<(String, HTTPMethod), RequestListener<'static>>,
-    public_folder: Option<PathBuf>,
-    not_found_callback: Box<dyn FnMut(HTTPRequest) -> HTTPResponse + Send + 'static>
+    router: Router,
+    public_folders: Vec<(String, PublicFolder)>,
+    compression: Option<Compression>,
+    mime_registry: MimeRegistry,
+    state: Arc<State>,
+    keep_alive: bool,
+    keep_alive_timeout: Duration,
+    max_requests_per_connection: usize,
+    max_body_size: usize,
+    thread_limit: usize,
+    not_found_callback: Box<dyn Fn(HTTPRequest) -> HTTPResponse + Send + Sync + 'static>
 }
 
 impl RequestHandler {
@@ -49,9 +54,17 @@ impl RequestHandler {
     /// ```
     pub fn new() -> Self {
         RequestHandler {
-            listeners: HashMap::new(),
-            public_folder: None,
-            not_found_callback: Box::new(Self::default404)
+            router: Router::new(),
+            public_folders: Vec::new(),
+            compression: None,
+            mime_registry: MimeRegistry::new(),
+            state: Arc::new(State::new()),
+            keep_alive: true,
+            keep_alive_timeout: Duration::from_secs(5),
+            max_requests_per_connection: 100,
+            max_body_size: 8 * 1024 * 1024,
+            thread_limit: 16,
+            not_found_callback: Box::new(Router::default404)
         }
     }
 
@@ -59,11 +72,18 @@ impl RequestHandler {
     /// 
     /// Sets a listener for a specific path and http method
     /// 
+    /// The path may contain parameter segments (`:name`) matching a single path segment,
+    /// and a trailing wildcard segment (`*name`) matching the rest of the path.
+    /// The captured values are available through `HTTPRequest::param`.
+    /// Request paths are split into segments before they are decoded, so `/users/a%2Fb` matches `/users/:id`.
+    /// 
+    /// When several paths match a request, static segments win over parameters and parameters over wildcards.
+    /// 
     /// ## Arguments
     /// 
     /// * `method` - The http method to be used on this path
     /// * `path` - The path to listen on
-    /// * `callback` - The callback to be called when a request is made to this path
+    /// * `callback` - The callback to be called when a request is made to this path, returning anything that implements `IntoResponse`
     /// 
     /// ## Example
     /// 
@@ -72,18 +92,108 @@ impl RequestHandler {
     /// use carola::http::{HTTPMethod, HTTPRequest, HTTPResponse};
     /// 
     /// let mut handler = RequestHandler::new();
-    /// handler.set_listener("GET", "/example", |request: HTTPRequest| {
-    ///    HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("Hello World!")))
+    /// handler.set_listener("GET", "/example", |request: HTTPRequest| "Hello World!");
+    /// handler.set_listener("GET", "/users/:id", |request: HTTPRequest| {
+    ///    let id = request.param("id")?;
+    ///    Some(format!("User {}", id))
     /// });
     /// ```
-    pub fn set_listener<C: 'static + Send + FnMut(HTTPRequest) -> HTTPResponse>(&mut self, method: &str, path: &str, callback: C) {
-        self.listeners.insert(
-            (path.to_owned(), HTTPMethod::from(method)),
-            RequestListener::new(
-            path.to_string(),
-            HTTPMethod::from(method),
-            callback
-        ));
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the path is malformed or conflicts with a listener that is already registered for the same method,
+    /// e.g. the same path twice or `/users/:id` next to `/users/:name`
+    pub fn set_listener<C, R>(&mut self, method: &str, path: &str, callback: C)
+    where
+        C: 'static + Send + Sync + Fn(HTTPRequest) -> R,
+        R: IntoResponse,
+    {
+        self.router.set_listener(method, path, callback);
+    }
+
+    /// # RequestHandler::mount
+    /// 
+    /// Mounts a router under a static prefix, so that its listeners are served at `prefix + path`
+    /// 
+    /// Listeners set directly on the handler take precedence over mounted routers, and longer prefixes over shorter ones.
+    /// Requests that the mounted router does not handle fall through to the public folder,
+    /// unless the router has its own not found callback.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `prefix` - The path prefix to mount the router on, e.g. `/api/v1`
+    /// * `router` - The router to mount
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use carola::router::Router;
+    /// 
+    /// let mut admin = Router::new();
+    /// admin.set_listener("GET", "/stats", |request: HTTPRequest| {
+    ///    HTTPResponse::new("1.1", HTTPStatusCode::OK, HeaderMap::new(), Some(String::from("Stats").into()))
+    /// });
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.mount("/admin", admin); // Serves GET /admin/stats
+    /// ```
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the prefix does not start with `/` or contains parameters
+    pub fn mount(&mut self, prefix: &str, router: Router) {
+        self.router.mount(prefix, router);
+    }
+
+    /// # RequestHandler::set_compression
+    /// 
+    /// Compresses response bodies with `gzip` or `deflate`, chosen by the `Accept-Encoding` header of the request
+    /// 
+    /// Compression is disabled by default. See `Compression` for which responses are compressed,
+    /// by default the text based types of the handler's `MimeRegistry`, even if it is set afterwards.
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use carola::http::Compression;
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_compression(Compression::new().with_min_size(512));
+    /// ```
+    pub fn set_compression(&mut self, mut compression: Compression) {
+        compression.use_registry(&self.mime_registry);
+        self.compression = Some(compression);
+    }
+
+    /// # RequestHandler::set_mime_registry
+    /// 
+    /// Sets the registry used to find the `Content-Type` of files in the public folder, `MimeRegistry::new()` by default
+    /// 
+    /// Its text based types are also the ones that are compressed, unless the `Compression` lists its own types.
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use carola::mime::MimeRegistry;
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_mime_registry(MimeRegistry::new().with_type("gltf", "model/gltf+json").with_sniffing(true));
+    /// ```
+    pub fn set_mime_registry(&mut self, registry: MimeRegistry) {
+        self.mime_registry = registry;
+        if let Some(compression) = &mut self.compression {
+            compression.use_registry(&self.mime_registry);
+        }
+    }
+
+    /// # RequestHandler::get_mime_registry
+    /// 
+    /// Returns the registry used for the `Content-Type` of files in the public folders and for the types that are compressed
+    pub fn get_mime_registry(&self) -> &MimeRegistry {
+        &self.mime_registry
     }
 
     /// # RequestHandler::set_public_folder
@@ -91,10 +201,14 @@ impl RequestHandler {
     /// Sets the public folder for the server which will be used to serve static files.
     /// 
     /// Files in this folder automatically have a lower priority than expicitly set listeners.
+    /// They are served for `GET` and `HEAD`, `OPTIONS` lists these methods and any other method is answered with `405 Method Not Allowed`.
+    /// Requests can not reach files outside of the folder, see `PublicFolder` for the options controlling symbolic links and dotfiles.
+    /// 
+    /// This mounts the folder at `/`, replacing the folder that was mounted there before, see `RequestHandler::mount_public_folder`.
     /// 
     /// ## Arguments
     /// 
-    /// * `path` - The path to the public folder, either an absolute path or one that is realtive to the project root
+    /// * `folder` - The path to the public folder, either an absolute path or one that is realtive to the project root, or a configured `PublicFolder`
     /// 
     /// ## Examples
     /// 
@@ -103,10 +217,60 @@ impl RequestHandler {
     /// 
     /// let mut handler = RequestHandler::new();
     /// handler.set_public_folder("./public"); // Will asign the public folder to the ./public folder
+    /// handler.set_public_folder(PublicFolder::new("./public").with_dotfile_policy(DotfilePolicy::Deny));
     /// ```
-    pub fn set_public_folder(&mut self, path: &str) {
-        let path = PathBuf::from(path);
-        self.public_folder = Some(path);
+    pub fn set_public_folder<F: Into<PublicFolder>>(&mut self, folder: F) {
+        self.mount_public_folder("/", folder);
+    }
+
+    /// # RequestHandler::mount_public_folder
+    /// 
+    /// Serves the files of a folder under a static prefix, so that `prefix + path` serves the file at `path` inside of the folder
+    /// 
+    /// Each folder has its own options, like the cache policy, index file, listing and dotfile rules, see `PublicFolder`.
+    /// Mounting a folder at a prefix that already has one replaces it.
+    /// 
+    /// Requests are matched in this order:
+    /// 1. Listeners, including those of mounted routers
+    /// 2. Public folders whose prefix matches the path, the longest prefix first.
+    ///    If a folder has no file for the path, the folder with the next shorter prefix is tried
+    /// 3. The single-page app fallback of the matching folders, see `PublicFolder::with_spa_fallback`
+    /// 4. The not found callback
+    /// 
+    /// A prefix only matches whole path segments, `/assets` matches `/assets/app.js` but not `/assets-old/app.js`.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `prefix` - The path prefix to serve the folder at, e.g. `/assets`
+    /// * `folder` - The path to the folder or a configured `PublicFolder`
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use carola::public_folder::PublicFolder;
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.mount_public_folder("/assets", PublicFolder::new("./dist").with_cache_control("public, max-age=31536000, immutable"));
+    /// handler.mount_public_folder("/uploads", PublicFolder::new("/var/data").with_listing(true).with_index_file(None));
+    /// ```
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the prefix does not start with `/` or contains parameters
+    pub fn mount_public_folder<F: Into<PublicFolder>>(&mut self, prefix: &str, folder: F) {
+        if !prefix.starts_with('/') || prefix.contains([':', '*']) {
+            panic!("invalid mount prefix '{}': prefixes must be static and start with '/'", prefix);
+        }
+
+        let prefix = prefix.trim_end_matches('/').to_string();
+        self.public_folders.retain(|(existing, _)| *existing != prefix);
+        let index = self
+            .public_folders
+            .iter()
+            .position(|(existing, _)| existing.len() < prefix.len())
+            .unwrap_or(self.public_folders.len());
+        self.public_folders.insert(index, (prefix, folder.into()));
     }
 
     /// # RequestHandler::set_not_found_callback
@@ -125,17 +289,158 @@ impl RequestHandler {
     /// 
     /// let mut handler = RequestHandler::new();
     /// handler.set_not_found_callback(|request: HTTPRequest| {
-    ///    HTTPResponse::new("1.1", HTTPStatusCode::NOT_FOUND, HashMap::new(), Some(String::from("404 Not Found!")))
+    ///    HTTPResponse::new("1.1", HTTPStatusCode::NOT_FOUND, HeaderMap::new(), Some(String::from("404 Not Found!").into()))
     /// });
     /// ```
-    pub fn set_not_found_callback<C: 'static + Send + FnMut(HTTPRequest) -> HTTPResponse>(&mut self, callback: C) {
+    pub fn set_not_found_callback<C: 'static + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>(&mut self, callback: C) {
         self.not_found_callback = Box::new(callback);
     }
 
+    /// # RequestHandler::set_method_not_allowed_callback
+    /// 
+    /// Sets the callback to be called when a request is made to a path that has listeners, but none for the requested method
+    /// 
+    /// The `Allow` header listing the methods registered for the path is added to the response automatically,
+    /// unless the callback sets it itself
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `callback` - The callback to be called when a request is made with a method that is not allowed on the path
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use carola::http::{HTTPMethod, HTTPRequest, HTTPResponse};
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_method_not_allowed_callback(|request: HTTPRequest| {
+    ///    HTTPResponse::new("1.1", HTTPStatusCode::MethodNotAllowed, HeaderMap::new(), Some(String::from("405 Method Not Allowed!").into()))
+    /// });
+    /// ```
+    pub fn set_method_not_allowed_callback<C: 'static + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>(&mut self, callback: C) {
+        self.router.set_method_not_allowed_callback(callback);
+    }
+
+    /// # RequestHandler::set_state
+    /// 
+    /// Stores a value that all listeners can access through `HTTPRequest::state`, replacing any existing value of the same type
+    /// 
+    /// Requests are handled concurrently, so values that listeners need to change should do their own locking,
+    /// e.g. a `Mutex` or an atomic
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `value` - The value to share between listeners
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use std::sync::Mutex;
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_state(Mutex::new(Vec::<String>::new()));
+    /// handler.set_listener("POST", "/messages", |request: HTTPRequest| {
+    ///    let messages = request.state::<Mutex<Vec<String>>>().unwrap();
+    ///    messages.lock().unwrap().push(request.get_body_str().unwrap_or_default().to_string());
+    ///    HTTPResponse::new("1.1", HTTPStatusCode::Created, HeaderMap::new(), None)
+    /// });
+    /// ```
+    pub fn set_state<T: Any + Send + Sync>(&mut self, value: T) {
+        Arc::make_mut(&mut self.state).insert(value);
+    }
+
+    /// # RequestHandler::set_keep_alive
+    /// 
+    /// Enables or disables persistent connections. Enabled by default.
+    /// 
+    /// When enabled, HTTP/1.1 connections stay open after a response unless the client sends `Connection: close`,
+    /// and HTTP/1.0 connections stay open if the client sends `Connection: keep-alive`.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `enabled` - Whether connections may serve more than one request
+    pub fn set_keep_alive(&mut self, enabled: bool) {
+        self.keep_alive = enabled;
+    }
+
+    /// # RequestHandler::set_keep_alive_timeout
+    /// 
+    /// Sets how long an idle connection is kept open while waiting for the next request. Defaults to 5 seconds.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `timeout` - The idle timeout, must not be zero
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// use carola::handler::RequestHandler;
+    /// use std::time::Duration;
+    /// 
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_keep_alive_timeout(Duration::from_secs(30));
+    /// ```
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the timeout is zero
+    pub fn set_keep_alive_timeout(&mut self, timeout: Duration) {
+        assert!(!timeout.is_zero());
+        self.keep_alive_timeout = timeout;
+    }
+
+    /// # RequestHandler::set_max_requests_per_connection
+    /// 
+    /// Sets how many requests a single connection may serve before it is closed. Defaults to 100.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `max` - The maximum number of requests per connection
+    pub fn set_max_requests_per_connection(&mut self, max: usize) {
+        self.max_requests_per_connection = max;
+    }
+
+    /// # RequestHandler::set_max_body_size
+    /// 
+    /// Sets the largest request body in bytes that is accepted. Defaults to 8 MiB.
+    /// 
+    /// Requests with a larger body are answered with `413 Payload Too Large`.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `size` - The maximum body size in bytes
+    pub fn set_max_body_size(&mut self, size: usize) {
+        self.max_body_size = size;
+    }
+
+    /// # RequestHandler::set_thread_limit
+    /// 
+    /// Sets the number of worker threads handling connections. Defaults to 16.
+    /// 
+    /// A connection holds its worker while it is kept alive, so only three quarters of the workers
+    /// keep connections open after a response. The others answer new clients and close their connections,
+    /// so that idle clients can not make everyone else wait.
+    /// 
+    /// ## Arguments
+    /// 
+    /// * `limit` - The number of worker threads
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the limit is zero
+    pub fn set_thread_limit(&mut self, limit: usize) {
+        assert!(limit > 0);
+        self.thread_limit = limit;
+    }
+
     /// # RequestHandler::listen
     /// 
     /// Activates the server and starts listening for requests. This method will take control of the current thread and will not return until the server is stopped.
     /// 
+    /// Requests are handled concurrently by a pool of worker threads sharing the handler, see `set_thread_limit`.
+    /// 
     /// ## Arguments
     /// 
     /// * `port` - The port to listen on
@@ -149,89 +454,242 @@ impl RequestHandler {
     /// // -- Configure handler --
     /// handler.listen(8080).unwrap();
     /// ```
-    pub fn listen(mut self, port: usize) -> Result<(), Box<dyn Error>> {
+    pub fn listen(self, port: usize) -> Result<(), Box<dyn Error>> {
         println!("{:?}", self);
-        let listener = TcpListener::bind(&format!("127.0.0.1:{}", port))?;
-        let thread_pool = ThreadPool::new(16);
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", port))?;
+        let thread_pool = ThreadPool::new(self.thread_limit);
 
-        let arc = Arc::new(Mutex::new(self));
+        let handler = Arc::new(self);
+        let kept_alive = Arc::new(AtomicUsize::new(0));
 
         for stream in listener.incoming() {
-            let arc = arc.clone();
+            let handler = handler.clone();
+            let kept_alive = kept_alive.clone();
             thread_pool.execute(move || {
-                let mut handler = arc.lock().unwrap();
                 let stream = stream.unwrap();
-                handler.handle_request(stream);
+                handler.handle_connection(stream, &kept_alive);
             });
         }
 
         unreachable!()
     }
 
-    fn handle_request(&mut self, mut stream: TcpStream) {
-        let buf_reader = BufReader::new(&mut stream);
-        let request = http::HTTPRequest::parse(buf_reader.lines().map(|l| l.unwrap()));
+    /// Serves the requests of a connection, `kept_alive` counts the connections that are held open between requests
+    fn handle_connection(&self, stream: TcpStream, kept_alive: &AtomicUsize) {
+        // The read timeout doubles as the idle timeout between requests
+        if stream.set_read_timeout(Some(self.keep_alive_timeout)).is_err() {
+            return;
+        }
+
+        let mut reader = BufReader::new(&stream);
+        let mut writer = &stream;
+        let mut handled = 0;
+        let mut slot = KeepAliveSlot { count: kept_alive, taken: false };
+        let max_kept_alive = self.thread_limit - (self.thread_limit / 4).max(1);
 
-        let response = match request {
-            Ok(req) => self.match_request(req),
-            Err(err) => {
-                HTTPResponse::new("1.1", HTTPStatusCode::BadRequest, HashMap::new(), Some(err))
+        loop {
+            // Stop once the client has closed the connection or the idle timeout has passed
+            match reader.fill_buf() {
+                Ok(buffer) if !buffer.is_empty() => {}
+                _ => break,
             }
-        }.construct();
-        println!("{}", response);
+            handled += 1;
+
+            let request = http::HTTPRequest::read(&mut reader, self.max_body_size);
 
-        stream.write_all(response.as_bytes()).unwrap();
+            let (mut response, keep_alive) = match request {
+                Ok(req) => {
+                    let keep_alive = self.keep_alive
+                        && handled < self.max_requests_per_connection
+                        && req.wants_keep_alive();
+                    let version = req.get_version().to_owned();
+                    let head = *req.get_method() == HTTPMethod::Head;
+                    println!("{} {}", req.get_method(), req.get_uri());
+
+                    // A panicking listener only costs its own connection, not the worker
+                    let (mut response, panicked) = match panic::catch_unwind(AssertUnwindSafe(|| self.match_request(req))) {
+                        Ok(response) => (response, false),
+                        Err(_) => (HTTPResponse::error(HTTPStatusCode::InternalServerError), true),
+                    };
+                    if version == "1.0" || version == "0.9" {
+                        response.disable_chunked();
+                    }
+                    let keep_alive = keep_alive
+                        && !panicked
+                        && !response.is_close_delimited()
+                        && !response.get_header("Connection").is_some_and(|value| value.eq_ignore_ascii_case("close"));
+                    // Keep some workers free for new connections
+                    let keep_alive = keep_alive && slot.take(max_kept_alive);
+                    Self::set_connection_headers(&mut response, &version, keep_alive, head);
+                    (response, keep_alive)
+                }
+                Err(err) => {
+                    let mut response = HTTPResponse::new("1.1", *err.get_status(), HeaderMap::new(), Some(err.to_string().into()));
+                    response.set_header("Connection", "close");
+                    (response, false)
+                }
+            };
+
+            if !keep_alive {
+                response.set_header("Connection", "close");
+            }
+
+            println!("{} {}", response.get_status().value(), response.get_status().message());
+
+            if response.write_to(&mut writer).is_err() || !keep_alive {
+                break;
+            }
+        }
+    }
+
+    fn set_connection_headers(response: &mut HTTPResponse, version: &str, keep_alive: bool, head: bool) {
+        if keep_alive && (version == "1.0" || version == "0.9") {
+            response.set_header("Connection", "keep-alive");
+        }
+
+        // Without a length the client could only find the end of the body by the connection closing
+        let has_body = !matches!(response.get_status().value(), 100..=199 | 204 | 304);
+        if keep_alive && has_body && !head && !response.has_body() && response.get_header("Content-Length").is_none() {
+            response.set_header("Content-Length", "0");
+        }
     }
 
-    fn match_request(&mut self, request: HTTPRequest) -> HTTPResponse {
-        let path = request.get_uri().to_owned();
-        let method = request.get_method().to_owned();
+    /// # RequestHandler::match_request
+    /// 
+    /// Matches a parsed request against the configured listeners and public folder and returns the response.
+    /// 
+    /// This is called for every incoming request by `listen`, but can also be used to test a handler without opening a socket.
+    /// 
+    /// `HEAD` and `OPTIONS` requests are answered automatically unless a listener is registered for them:
+    /// `HEAD` runs the `GET` listener and drops the body, `OPTIONS` lists the methods allowed on the path.
+    ///
+    /// Responses that do not set a version of their own are answered with the version of the request.
+    pub fn match_request(&self, request: HTTPRequest) -> HTTPResponse {
+        let version = request.get_version().to_owned();
+        let accept_encoding = request.get_header("Accept-Encoding").map(String::from);
+        let head = *request.get_method() == HTTPMethod::Head;
+        let mut response = self.respond(request);
+        response.set_default_version(&version);
 
-        // If there is a listener for this path, call it
-        if let Some(listener) = self.listeners.get_mut(&(path, method)) {
-            (listener.callback)(request)
+        if let Some(compression) = &self.compression {
+            compression.apply(accept_encoding.as_deref(), &mut response);
         }
-        // If there is a public folder and the file exists, serve it
-        else if let Some(public_folder) = &self.public_folder {
-            let path = public_folder.join(request.get_uri());
-            if path.exists() {
-                let mut headers = HashMap::new();
 
-                // Define content type based on file extension
-                let content_type = match path.extension() {
-                    None => "text/plain",
-                    Some(ext) => get_supported_filetypes().get(ext).unwrap_or(&"text/plain")
-                };
+        // Whichever listener, folder or callback answered, a response to HEAD never has a body,
+        // but its headers, including the compression, are the ones a GET would get
+        if head {
+            response.strip_body();
+        }
+        response
+    }
+
+    fn respond(&self, mut request: HTTPRequest) -> HTTPResponse {
+        request.set_state(self.state.clone());
 
-                headers.insert("Content-Type".to_string(), content_type.to_string());
+        let path = request.get_route_path().to_owned();
+        let request = match self.router.route(&path, request) {
+            Ok(response) => return response,
+            Err(request) => request,
+        };
 
-                // Read file contents
-                let body = fs::read_to_string(path).unwrap();
+        // If a public folder mounted at the path has the file, serve it
+        for (prefix, public_folder) in &self.public_folders {
+            if !is_under_prefix(request.get_path(), prefix) {
+                continue;
+            }
+            // The folder itself is left empty, so that it is redirected to the path with a trailing slash like any other directory
+            let path = &request.get_path()[prefix.len()..];
+            if let Some(response) = public_folder.serve(&request, path, &self.mime_registry) {
+                return self.restrict_file_methods(request, response);
+            }
+        }
 
-                // Construct response
-                HTTPResponse::new("1.1", HTTPStatusCode::OK, headers, Some(body))
+        // Single-page apps handle paths without a file themselves
+        for (prefix, public_folder) in &self.public_folders {
+            if !is_under_prefix(request.get_path(), prefix) {
+                continue;
             }
-            else {
-                (self.not_found_callback)(request)
+            if let Some(response) = public_folder.serve_fallback(&request, &self.mime_registry) {
+                return response;
             }
         }
-        // If there is no listener and no public folder, return 404
-        else {
-            (self.not_found_callback)(request)
+
+        // If there is no listener and no matching file, return 404
+        (self.not_found_callback)(request)
+    }
+
+    /// Files can only be read, other methods are answered like on a path that only has a `GET` listener
+    fn restrict_file_methods(&self, request: HTTPRequest, response: HTTPResponse) -> HTTPResponse {
+        const ALLOW: &str = "GET, HEAD, OPTIONS";
+
+        match request.get_method() {
+            HTTPMethod::Get | HTTPMethod::Head => response,
+            _ if *response.get_status() == HTTPStatusCode::Forbidden => response,
+            HTTPMethod::Options => HTTPResponse::builder()
+                .status(HTTPStatusCode::NoContent)
+                .header("Allow", ALLOW)
+                .build(),
+            _ => self.router.method_not_allowed(request, ALLOW),
         }
     }
 
-    fn default404(_: HTTPRequest) -> HTTPResponse {
-        let body = include_str!("../assets/404.html").to_string();
-        HTTPResponse::new("1.1", HTTPStatusCode::NotFound, HashMap::new(), Some(body))
+    /// # RequestHandler::allowed_methods
+    /// 
+    /// Returns the methods that have a listener matching the given path, in a stable order
+    /// 
+    /// This includes `HEAD` and `OPTIONS` when they are handled automatically
+    pub fn allowed_methods(&self, path: &str) -> Vec<HTTPMethod> {
+        self.router.allowed_methods(path)
+    }
+}
+
+/// The place of a connection among the ones kept alive, given back when the connection ends, even by a panic
+struct KeepAliveSlot<'a> {
+    count: &'a AtomicUsize,
+    taken: bool,
+}
+
+impl KeepAliveSlot<'_> {
+    /// Takes a place unless there are already `max` connections kept alive, returns whether the connection has one
+    fn take(&mut self, max: usize) -> bool {
+        if !self.taken {
+            self.taken = self
+                .count
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| (count < max).then_some(count + 1))
+                .is_ok();
+        }
+        self.taken
+    }
+}
+
+impl Drop for KeepAliveSlot<'_> {
+    fn drop(&mut self) {
+        if self.taken {
+            self.count.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Returns whether the path is the prefix or below it, prefixes only match whole segments
+fn is_under_prefix(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+impl Default for RequestHandler {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Debug for RequestHandler {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RequestHandler")
-            .field("listeners", &self.listeners)
-            .field("public_folder", &self.public_folder)
+            .field("router", &self.router)
+            .field("public_folders", &self.public_folders)
+            .field("state", &self.state)
             .finish()
     }
 }
\ No newline at end of file
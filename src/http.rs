@@ -1,11 +1,31 @@
 pub mod request;
-pub use request::HTTPRequest;
+pub use request::{HTTPRequest, ParseError};
 
 pub mod method;
 pub use method::HTTPMethod;
 
+pub mod header;
+pub use header::{HeaderError, HeaderMap};
+
 pub mod response;
-pub use response::HTTPResponse;
+pub use response::{HTTPResponse, ResponseBuilder};
+
+pub mod into_response;
+pub use into_response::IntoResponse;
 
 pub mod status_code;
-pub use status_code::HTTPStatusCode;
\ No newline at end of file
+pub use status_code::HTTPStatusCode;
+
+pub mod body;
+pub use body::{BodySender, BodyStream};
+
+pub mod compression;
+pub use compression::Compression;
+
+pub mod date;
+
+pub mod encoding;
+
+pub mod url;
+
+mod chunked;
\ No newline at end of file
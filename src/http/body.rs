@@ -0,0 +1,353 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Write},
+    sync::mpsc::{self, Receiver, SyncSender},
+};
+
+use super::{compression::Encoder, HeaderMap};
+
+/// The size of the buffer used to copy from readers, which is also the largest chunk sent for them
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// How many chunks a `BodySender` can queue before `send` blocks until the client has caught up
+const CHANNEL_CAPACITY: usize = 16;
+
+/// # BodyStream
+///
+/// A response body that is written to the client while it is produced instead of being built in memory first
+///
+/// If the length of the body is known it is sent with a `Content-Length` header,
+/// otherwise with `Transfer-Encoding: chunked`. Clients that do not support chunked responses (HTTP/1.0)
+/// receive the body as is, and the connection is closed to mark its end.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::http::{BodyStream, HTTPResponse, HTTPStatusCode, HeaderMap};
+///
+/// let export = (0..1000).map(|i| format!("{},{}\n", i, i * i).into_bytes());
+/// HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HeaderMap::new(), BodyStream::from_chunks(export))
+/// ```
+pub struct BodyStream {
+    source: Source,
+    length: Option<u64>,
+    trailers: HeaderMap,
+    encoder: Option<Encoder>,
+}
+
+enum Source {
+    Reader(Box<dyn Read + Send>),
+    Chunks(Box<dyn Iterator<Item = Vec<u8>> + Send>),
+    Channel(Receiver<Message>),
+}
+
+enum Message {
+    Chunk(Vec<u8>),
+    Trailer(String, String),
+}
+
+/// # BodySender
+///
+/// The sending half of `BodyStream::channel`, used to produce a body chunk by chunk, e.g. from another thread
+///
+/// The body ends when the sender is dropped.
+pub struct BodySender {
+    sender: SyncSender<Message>,
+}
+
+/// How the end of a body is communicated to the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Framing {
+    /// The body is exactly this long
+    Length(u64),
+    /// The body is split into chunks ending with an empty chunk
+    Chunked,
+    /// The body ends when the connection is closed
+    Close,
+}
+
+impl BodyStream {
+    /// # BodyStream::from_reader
+    ///
+    /// Streams the body from a reader
+    ///
+    /// ## Arguments
+    ///
+    /// * `reader` - The reader to stream the body from
+    /// * `length` - The number of bytes the reader will provide, if known
+    pub fn from_reader<R: Read + Send + 'static>(reader: R, length: Option<u64>) -> Self {
+        Self {
+            source: Source::Reader(Box::new(reader)),
+            length,
+            trailers: HeaderMap::new(),
+            encoder: None,
+        }
+    }
+
+    /// # BodyStream::from_file
+    ///
+    /// Streams the body from a file, using its size as the length
+    pub fn from_file(file: File) -> io::Result<Self> {
+        let length = file.metadata()?.len();
+        Ok(Self::from_reader(file, Some(length)))
+    }
+
+    /// # BodyStream::from_chunks
+    ///
+    /// Streams the body from an iterator of chunks, each of which is sent as soon as it is produced
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        Self {
+            source: Source::Chunks(Box::new(chunks.into_iter())),
+            length: None,
+            trailers: HeaderMap::new(),
+            encoder: None,
+        }
+    }
+
+    /// # BodyStream::channel
+    ///
+    /// Creates a body that is produced through a `BodySender`, every chunk is sent to the client as soon as it is produced
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// handler.set_listener("GET", "/events", |_: HTTPRequest| {
+    ///     let (sender, body) = BodyStream::channel();
+    ///     thread::spawn(move || {
+    ///         for event in events() {
+    ///             if sender.send(event.to_string()).is_err() {
+    ///                 break; // The client has disconnected
+    ///             }
+    ///         }
+    ///         sender.trailer("X-Event-Count", &events().count().to_string());
+    ///     });
+    ///     HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HeaderMap::new(), body)
+    /// });
+    /// ```
+    pub fn channel() -> (BodySender, Self) {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let stream = Self {
+            source: Source::Channel(receiver),
+            length: None,
+            trailers: HeaderMap::new(),
+            encoder: None,
+        };
+
+        (BodySender { sender }, stream)
+    }
+
+    /// # BodyStream::with_trailer
+    ///
+    /// Adds a trailer field that is sent after the body
+    ///
+    /// Trailers are only sent with `Transfer-Encoding: chunked`, they are dropped if the length of the body is known
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the name or value is not a valid header, see `HeaderMap::append`
+    pub fn with_trailer(mut self, name: &str, value: &str) -> Self {
+        self.trailers.append(name, value);
+        self
+    }
+
+    /// Names of the trailers known before the body is sent, announced in the `Trailer` header
+    pub(crate) fn get_trailer_names(&self) -> Vec<&str> {
+        let mut names = self.trailers.iter().map(|(name, _)| name).collect::<Vec<_>>();
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names
+    }
+
+    /// # BodyStream::with_length
+    ///
+    /// Sets the total length of the body, so that it can be sent with a `Content-Length` header
+    pub fn with_length(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// # BodyStream::get_length
+    ///
+    /// Returns the total length of the body, or `None` if it is only known once the stream has ended
+    pub fn get_length(&self) -> Option<u64> {
+        self.length
+    }
+
+    /// Compresses the body while it is written, the length of the compressed body is not known up front
+    pub(crate) fn encode(mut self, encoder: Encoder) -> Self {
+        self.encoder = Some(encoder);
+        self.length = None;
+        self
+    }
+
+    /// Writes the body with the given framing, flushing the writer after every chunk
+    pub(crate) fn write_to<W: Write>(self, writer: &mut W, framing: Framing) -> io::Result<()> {
+        let mut body = BodyWriter {
+            writer,
+            framing,
+            written: 0,
+            trailers: self.trailers,
+            encoder: self.encoder,
+        };
+
+        match self.source {
+            Source::Reader(mut reader) => {
+                let mut buffer = vec![0; BUFFER_SIZE];
+                loop {
+                    let read = match reader.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(read) => read,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(err),
+                    };
+                    body.write_chunk(&buffer[..read])?;
+                }
+            }
+            Source::Chunks(chunks) => {
+                for chunk in chunks {
+                    body.write_chunk(&chunk)?;
+                }
+            }
+            Source::Channel(receiver) => {
+                for message in receiver {
+                    match message {
+                        Message::Chunk(chunk) => body.write_chunk(&chunk)?,
+                        Message::Trailer(name, value) => body.trailers.append(&name, &value),
+                    }
+                }
+            }
+        }
+
+        body.finish()
+    }
+}
+
+impl BodySender {
+    /// # BodySender::send
+    ///
+    /// Sends a chunk of the body, blocking while too many chunks are waiting to be written
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the body is no longer being written, e.g. because the client has disconnected
+    pub fn send<B: Into<Vec<u8>>>(&self, chunk: B) -> io::Result<()> {
+        self.sender
+            .send(Message::Chunk(chunk.into()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Body is no longer being written"))
+    }
+
+    /// # BodySender::trailer
+    ///
+    /// Adds a trailer field that is sent after the body, only used with `Transfer-Encoding: chunked`
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the name or value is not a valid header, or if the body is no longer being written
+    pub fn trailer(&self, name: &str, value: &str) -> io::Result<()> {
+        HeaderMap::new()
+            .try_insert(name, value)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        self.sender
+            .send(Message::Trailer(name.to_string(), value.to_string()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Body is no longer being written"))
+    }
+}
+
+impl Write for BodySender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.send(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct BodyWriter<'w, W: Write> {
+    writer: &'w mut W,
+    framing: Framing,
+    written: u64,
+    trailers: HeaderMap,
+    encoder: Option<Encoder>,
+}
+
+impl<W: Write> BodyWriter<'_, W> {
+    fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        match &mut self.encoder {
+            Some(encoder) if !chunk.is_empty() => {
+                let encoded = encoder.write_chunk(chunk);
+                self.write_raw(&encoded)
+            }
+            _ => self.write_raw(chunk),
+        }
+    }
+
+    fn write_raw(&mut self, chunk: &[u8]) -> io::Result<()> {
+        // An empty chunk would end a chunked body early
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        self.written += chunk.len() as u64;
+
+        match self.framing {
+            Framing::Length(length) => {
+                if self.written > length {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Body is longer than its Content-Length"));
+                }
+                self.writer.write_all(chunk)?;
+            }
+            Framing::Chunked => {
+                write!(self.writer, "{:X}\r\n", chunk.len())?;
+                self.writer.write_all(chunk)?;
+                self.writer.write_all(b"\r\n")?;
+            }
+            Framing::Close => self.writer.write_all(chunk)?,
+        }
+
+        self.writer.flush()
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        if let Some(encoder) = self.encoder.take() {
+            self.write_raw(&encoder.finish())?;
+        }
+
+        match self.framing {
+            Framing::Length(length) if self.written < length => {
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Body is shorter than its Content-Length"))
+            }
+            Framing::Length(_) | Framing::Close => Ok(()),
+            Framing::Chunked => {
+                self.writer.write_all(b"0\r\n")?;
+                for (name, value) in self.trailers.iter() {
+                    write!(self.writer, "{}:{}\r\n", name, value)?;
+                }
+                self.writer.write_all(b"\r\n")?;
+                self.writer.flush()
+            }
+        }
+    }
+}
+
+impl Debug for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self.source {
+            Source::Reader(_) => "Reader",
+            Source::Chunks(_) => "Chunks",
+            Source::Channel(_) => "Channel",
+        };
+
+        f.debug_struct("BodyStream")
+            .field("source", &source)
+            .field("length", &self.length)
+            .field("trailers", &self.trailers)
+            .field("encoder", &self.encoder.as_ref().map(|encoder| encoder.get_coding()))
+            .finish()
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::{BufRead, Read};
+
+use super::{
+    request::{parse_header_line, read_line, ParseError, MAX_HEAD_SIZE},
+    HTTPStatusCode, HeaderMap,
+};
+
+/// # chunked::decode
+///
+/// Decodes a body sent with `Transfer-Encoding: chunked`, returning the body and the trailer fields
+///
+/// Chunk extensions are ignored. Malformed framing is rejected with `400 Bad Request`
+/// and bodies larger than `max_body_size` with `413 Payload Too Large`
+pub fn decode<R: BufRead>(reader: &mut R, max_body_size: usize) -> Result<(Vec<u8>, HeaderMap), ParseError> {
+    // Budget for the chunk size lines and trailers, which are not part of the body
+    let mut remaining = MAX_HEAD_SIZE;
+    let mut body = Vec::new();
+
+    loop {
+        let line = match read_line(reader, &mut remaining)? {
+            Some(line) => line,
+            None => return Err(String::from("Incomplete body").into()),
+        };
+
+        // chunk-size [ ; chunk-ext ]
+        let size = line.split(';').next().unwrap_or_default().trim_end_matches([' ', '\t']);
+        if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(String::from("Invalid chunk size").into());
+        }
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| ParseError::new(HTTPStatusCode::PayloadTooLarge, "Request body is too large"))?;
+
+        if size == 0 {
+            break;
+        }
+        if body.len().saturating_add(size) > max_body_size {
+            return Err(ParseError::new(HTTPStatusCode::PayloadTooLarge, "Request body is too large"));
+        }
+
+        let start = body.len();
+        reader
+            .take(size as u64)
+            .read_to_end(&mut body)
+            .map_err(|_| String::from("Incomplete body"))?;
+        if body.len() - start < size {
+            return Err(String::from("Incomplete body").into());
+        }
+
+        // Every chunk is followed by exactly one CRLF
+        let mut end = [0; 2];
+        reader
+            .read_exact(&mut end)
+            .map_err(|_| String::from("Incomplete body"))?;
+        if &end != b"\r\n" {
+            return Err(String::from("Invalid chunk").into());
+        }
+    }
+
+    // The trailer section ends with an empty line
+    let mut trailers = HeaderMap::new();
+    loop {
+        match read_line(reader, &mut remaining)? {
+            Some(line) if line.is_empty() => break,
+            Some(line) => {
+                let (key, value) = parse_header_line(&line)?;
+                trailers
+                    .try_append(&key, &value)
+                    .map_err(|err| err.to_string())?;
+            }
+            None => return Err(String::from("Incomplete body").into()),
+        }
+    }
+
+    Ok((body, trailers))
+}
@@ -0,0 +1,331 @@
+use crate::mime::MimeRegistry;
+
+use self::deflate::Deflater;
+use super::{encoding, HTTPResponse, HTTPStatusCode};
+
+mod deflate;
+
+/// # Compression
+///
+/// Compresses response bodies with `gzip` or `deflate` for clients that accept it, set with `RequestHandler::set_compression`
+///
+/// Only bodies with a compressible `Content-Type` and at least the minimum size are compressed. Unless they are replaced
+/// with `with_mime_types`, the compressible types are the text based types of the handler's `MimeRegistry`. Streamed bodies
+/// of unknown length are compressed while they are sent, every chunk is flushed so that it reaches the client right away.
+/// Responses that already have a `Content-Encoding`, like precompressed files, are left as they are.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::http::Compression;
+///
+/// let mut handler = RequestHandler::new();
+/// handler.set_compression(
+///     Compression::new()
+///         .with_min_size(4096)
+///         .with_mime_type("text/csv"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compression {
+    mime_types: Vec<String>,
+    /// The types added with `with_mime_type`, kept when the types are taken from another registry
+    added_types: Vec<String>,
+    from_registry: bool,
+    min_size: u64,
+}
+
+/// The content codings a body can be compressed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Coding {
+    Gzip,
+    Deflate,
+}
+
+/// Compresses a body in the format of its content coding, either at once or chunk by chunk
+pub(crate) struct Encoder {
+    coding: Coding,
+    deflater: Deflater,
+    header: Vec<u8>,
+    checksum: u32,
+    size: u32,
+}
+
+impl Compression {
+    /// # Compression::new
+    ///
+    /// Creates a configuration compressing the text based types of the `MimeRegistry`,
+    /// like html, css, javascript, json and svg, if the body has at least 1 KiB
+    ///
+    /// The types are taken from the registry of the handler once it is set with `RequestHandler::set_compression`
+    pub fn new() -> Self {
+        let mut compression = Self {
+            mime_types: Vec::new(),
+            added_types: Vec::new(),
+            from_registry: true,
+            min_size: 1024,
+        };
+        compression.use_registry(&MimeRegistry::new());
+        compression
+    }
+
+    /// # Compression::with_mime_types
+    ///
+    /// Replaces the types that are compressed, parameters like `charset` are ignored when a `Content-Type` is compared to them
+    pub fn with_mime_types<I, S>(mut self, mime_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.mime_types = mime_types.into_iter().map(Into::into).collect();
+        self.added_types.clear();
+        self.from_registry = false;
+        self
+    }
+
+    /// # Compression::with_mime_type
+    ///
+    /// Adds a type to the types that are compressed
+    pub fn with_mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_types.push(mime_type.to_string());
+        self.added_types.push(mime_type.to_string());
+        self
+    }
+
+    /// # Compression::with_min_size
+    ///
+    /// Sets the size in bytes below which bodies are sent uncompressed, 1024 by default.
+    /// Streamed bodies of unknown length are always compressed
+    pub fn with_min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// # Compression::get_mime_types
+    ///
+    /// Returns the types that are compressed, taken from the handler's registry once the compression is set on it
+    pub fn get_mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    /// # Compression::get_min_size
+    ///
+    /// Returns the size in bytes below which bodies are sent uncompressed
+    pub fn get_min_size(&self) -> u64 {
+        self.min_size
+    }
+
+    /// Takes the compressible types from the given registry, unless they were replaced with `with_mime_types`
+    pub(crate) fn use_registry(&mut self, registry: &MimeRegistry) {
+        if !self.from_registry {
+            return;
+        }
+
+        self.mime_types = registry
+            .mime_types()
+            .into_iter()
+            .filter(|mime_type| is_text(mime_type))
+            .map(String::from)
+            .collect();
+        for mime_type in &self.added_types {
+            if !self.mime_types.contains(mime_type) {
+                self.mime_types.push(mime_type.clone());
+            }
+        }
+    }
+
+    /// Compresses the body of a response if it qualifies and the client accepts one of the codings
+    pub(crate) fn apply(&self, accept_encoding: Option<&str>, response: &mut HTTPResponse) {
+        if response.get_header("Content-Encoding").is_some() || !response.has_body() {
+            return;
+        }
+        // Ranges refer to the bytes of the uncompressed body
+        if matches!(*response.get_status(), HTTPStatusCode::PartialContent | HTTPStatusCode::NotModified) {
+            return;
+        }
+
+        let mime_type = response
+            .get_header("Content-Type")
+            .and_then(|content_type| content_type.split(';').next())
+            .map(str::trim)
+            .unwrap_or_default();
+        if !self.mime_types.iter().any(|compressible| compressible.eq_ignore_ascii_case(mime_type)) {
+            return;
+        }
+
+        let size = match (response.get_body(), response.get_stream()) {
+            (Some(body), _) => Some(body.len() as u64),
+            (None, Some(stream)) => stream.get_length(),
+            (None, None) => None,
+        };
+        if size.is_some_and(|size| size < self.min_size) {
+            return;
+        }
+
+        // Whether the body is compressed now depends on the request, caches have to know that
+        add_vary(response);
+
+        let gzip = encoding::quality(accept_encoding, "gzip");
+        let deflate = encoding::quality(accept_encoding, "deflate");
+        let coding = if gzip > 0.0 && gzip >= deflate {
+            Coding::Gzip
+        } else if deflate > 0.0 {
+            Coding::Deflate
+        } else {
+            return;
+        };
+
+        response.encode_body(coding);
+
+        // The compressed body is a different sequence of bytes, but still equivalent to the original one
+        if let Some(etag) = response.get_header("ETag").filter(|etag| !etag.starts_with("W/")) {
+            let etag = format!("W/{}", etag);
+            response.set_header("ETag", &etag);
+        }
+    }
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Coding {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        }
+    }
+}
+
+impl Encoder {
+    pub(crate) fn new(coding: Coding) -> Self {
+        let (header, checksum) = match coding {
+            // No file name and modification time, unknown operating system
+            Coding::Gzip => (vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 0xFF], 0),
+            // 32 KiB window, no preset dictionary
+            Coding::Deflate => (vec![0x78, 0x01], 1),
+        };
+
+        Self {
+            coding,
+            deflater: Deflater::new(),
+            header,
+            checksum,
+            size: 0,
+        }
+    }
+
+    pub(crate) fn get_coding(&self) -> Coding {
+        self.coding
+    }
+
+    /// Compresses a whole body
+    pub(crate) fn encode(coding: Coding, body: &[u8]) -> Vec<u8> {
+        let mut encoder = Self::new(coding);
+        encoder.update(body);
+        encoder.deflater.write(body);
+        encoder.finish()
+    }
+
+    /// Compresses a chunk of a streamed body, returning output that can be decompressed up to the end of the chunk
+    pub(crate) fn write_chunk(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.update(chunk);
+        self.deflater.write(chunk);
+        self.deflater.sync_flush();
+        self.take_output()
+    }
+
+    /// Ends the body, returning the remaining output
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.deflater.finish();
+        let mut output = self.take_output();
+
+        match self.coding {
+            Coding::Gzip => {
+                output.extend_from_slice(&self.checksum.to_le_bytes());
+                output.extend_from_slice(&self.size.to_le_bytes());
+            }
+            Coding::Deflate => output.extend_from_slice(&self.checksum.to_be_bytes()),
+        }
+        output
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.size = self.size.wrapping_add(data.len() as u32);
+        self.checksum = match self.coding {
+            Coding::Gzip => crc32(self.checksum, data),
+            Coding::Deflate => adler32(self.checksum, data),
+        };
+    }
+
+    fn take_output(&mut self) -> Vec<u8> {
+        let mut output = std::mem::take(&mut self.header);
+        output.extend(self.deflater.take_output());
+        output
+    }
+}
+
+fn is_text(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || mime_type.ends_with("+xml")
+        || mime_type.ends_with("+json")
+        || matches!(mime_type, "application/json" | "application/xml" | "application/javascript" | "application/wasm")
+}
+
+fn add_vary(response: &mut HTTPResponse) {
+    let vary = match response.get_header("Vary") {
+        None => String::from("Accept-Encoding"),
+        Some(vary) if vary.split(',').any(|field| matches!(field.trim().to_ascii_lowercase().as_str(), "accept-encoding" | "*")) => {
+            return
+        }
+        Some(vary) => format!("{}, Accept-Encoding", vary),
+    };
+    response.set_header("Vary", &vary);
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+/// The CRC-32 used by gzip, see RFC 1952 section 8
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for byte in data {
+        crc = CRC_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// The Adler-32 checksum used by zlib, see RFC 1950 section 8
+fn adler32(adler: u32, data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+
+    let (mut a, mut b) = (adler & 0xFFFF, adler >> 16);
+    // The sums can not overflow within 5552 bytes
+    for block in data.chunks(5552) {
+        for byte in block {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
@@ -0,0 +1,224 @@
+/// How far back a match may reach
+const WINDOW_SIZE: usize = 32 * 1024;
+
+/// The amount of input compressed into a single block
+const BLOCK_SIZE: usize = 64 * 1024;
+
+const HASH_BITS: u32 = 15;
+const HASH_SIZE: usize = 1 << HASH_BITS;
+
+/// How many earlier positions with the same hash are compared before giving up, trading speed for compression
+const MAX_CHAIN: usize = 64;
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+const NONE: usize = usize::MAX;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// A DEFLATE encoder (RFC 1951) using LZ77 with hash chains and the fixed Huffman codes
+///
+/// Data can be written in pieces, the window and the hash chains are kept to find matches across them
+pub(super) struct Deflater {
+    /// The end of the data written so far, at least the last `WINDOW_SIZE` bytes of it
+    window: Vec<u8>,
+    /// The position of the first byte of `window` in the whole input
+    offset: usize,
+    /// The most recent position for every hash
+    head: Vec<usize>,
+    /// The previous position with the same hash, indexed by the position modulo `WINDOW_SIZE`
+    prev: Vec<usize>,
+    /// Positions before this one are in the hash chains
+    inserted: usize,
+    bits: BitWriter,
+}
+
+impl Deflater {
+    pub(super) fn new() -> Self {
+        Self {
+            window: Vec::with_capacity(2 * WINDOW_SIZE),
+            offset: 0,
+            head: vec![NONE; HASH_SIZE],
+            prev: vec![NONE; WINDOW_SIZE],
+            inserted: 0,
+            bits: BitWriter::default(),
+        }
+    }
+
+    /// Compresses `input` into one or more blocks, whose bytes are returned by `take_output`
+    pub(super) fn write(&mut self, input: &[u8]) {
+        for block in input.chunks(BLOCK_SIZE) {
+            self.write_block(block);
+        }
+    }
+
+    /// Ends the output at a byte boundary with an empty stored block, so that everything written so far can be decompressed
+    pub(super) fn sync_flush(&mut self) {
+        self.bits.write(0, 1);
+        self.bits.write(0, 2);
+        self.bits.align();
+        self.bits.write(0x0000, 16);
+        self.bits.write(0xFFFF, 16);
+    }
+
+    /// Ends the stream with an empty final block
+    pub(super) fn finish(&mut self) {
+        self.bits.write(1, 1);
+        self.bits.write(1, 2);
+        self.write_symbol(256);
+        self.bits.align();
+    }
+
+    /// Returns the complete bytes produced since the last call
+    pub(super) fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.bits.output)
+    }
+
+    fn write_block(&mut self, input: &[u8]) {
+        let mut pos = self.offset + self.window.len();
+        self.window.extend_from_slice(input);
+        let end = self.offset + self.window.len();
+
+        // The last bytes of the previous block can be hashed now that the bytes following them are known
+        self.insert_until(pos);
+
+        // Not the final block, compressed with the fixed Huffman codes
+        self.bits.write(0, 1);
+        self.bits.write(1, 2);
+
+        while pos < end {
+            match self.longest_match(pos) {
+                Some((length, distance)) => {
+                    self.write_match(length, distance);
+                    pos += length;
+                }
+                None => {
+                    self.write_symbol(self.window[pos - self.offset] as u16);
+                    pos += 1;
+                }
+            }
+            self.insert_until(pos);
+        }
+        self.write_symbol(256);
+
+        // Only drop old data once a whole window has piled up, so that it is not moved for every small block
+        if self.window.len() >= 2 * WINDOW_SIZE {
+            let drop = self.window.len() - WINDOW_SIZE;
+            self.window.drain(..drop);
+            self.offset += drop;
+        }
+    }
+
+    /// Adds the positions up to `end` to the hash chains, as far as there are enough bytes to hash them
+    fn insert_until(&mut self, end: usize) {
+        let available = (self.offset + self.window.len()).saturating_sub(MIN_MATCH - 1);
+        while self.inserted < end.min(available) {
+            let pos = self.inserted;
+            let hash = hash(&self.window, pos - self.offset);
+            self.prev[pos % WINDOW_SIZE] = self.head[hash];
+            self.head[hash] = pos;
+            self.inserted += 1;
+        }
+    }
+
+    /// Returns the length and distance of the longest earlier occurrence of the data at `pos`
+    fn longest_match(&self, pos: usize) -> Option<(usize, usize)> {
+        let data = &self.window;
+        let index = pos - self.offset;
+        if index + MIN_MATCH > data.len() {
+            return None;
+        }
+
+        let max_length = MAX_MATCH.min(data.len() - index);
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.head[hash(data, index)];
+
+        for _ in 0..MAX_CHAIN {
+            // Older positions may have been dropped from the window, or their chain entries reused
+            if candidate == NONE || pos - candidate > WINDOW_SIZE {
+                break;
+            }
+
+            let length = data[candidate - self.offset..]
+                .iter()
+                .zip(&data[index..index + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length >= MIN_MATCH && best.is_none_or(|(best, _)| length > best) {
+                best = Some((length, pos - candidate));
+                if length == max_length {
+                    break;
+                }
+            }
+            candidate = self.prev[candidate % WINDOW_SIZE];
+        }
+        best
+    }
+
+    fn write_match(&mut self, length: usize, distance: usize) {
+        let index = LENGTH_BASE.iter().rposition(|&base| base as usize <= length).unwrap_or_default();
+        self.write_symbol(257 + index as u16);
+        self.bits.write((length - LENGTH_BASE[index] as usize) as u32, LENGTH_EXTRA[index] as u32);
+
+        let index = DISTANCE_BASE.iter().rposition(|&base| base as usize <= distance).unwrap_or_default();
+        self.bits.write(reverse(index as u32, 5), 5);
+        self.bits.write((distance - DISTANCE_BASE[index] as usize) as u32, DISTANCE_EXTRA[index] as u32);
+    }
+
+    /// Writes a literal/length symbol with its fixed Huffman code, see RFC 1951 section 3.2.6
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        let (code, length) = match symbol {
+            0..=143 => (0x30 + symbol, 8),
+            144..=255 => (0x190 + symbol - 144, 9),
+            256..=279 => (symbol - 256, 7),
+            _ => (0xC0 + symbol - 280, 8),
+        };
+        self.bits.write(reverse(code, length), length);
+    }
+}
+
+fn hash(data: &[u8], pos: usize) -> usize {
+    let value = (data[pos] as usize) << 10 ^ (data[pos + 1] as usize) << 5 ^ data[pos + 2] as usize;
+    value & (HASH_SIZE - 1)
+}
+
+/// Huffman codes are stored starting with their most significant bit, while all other values start with the least significant one
+fn reverse(code: u32, length: u32) -> u32 {
+    code.reverse_bits() >> (32 - length)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    output: Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+/// # encoding::quality
+///
+/// Returns the quality value between 0 and 1 an `Accept-Encoding` header gives a content coding, see RFC 9110 section 12.5.3.
+///
+/// A coding that is listed by name takes its own value over the one of `*`, and `x-gzip` is treated as `gzip`.
+/// `identity` is acceptable unless it is excluded, while a missing header accepts nothing but `identity`.
+/// A quality of 0 means the coding must not be used
+pub fn quality(accept_encoding: Option<&str>, coding: &str) -> f32 {
+    let is_identity = coding.eq_ignore_ascii_case("identity");
+    let accept_encoding = match accept_encoding {
+        Some(accept_encoding) => accept_encoding,
+        None => return if is_identity { 1.0 } else { 0.0 },
+    };
+
+    let mut wildcard = None;
+    for entry in accept_encoding.split(',') {
+        let mut parts = entry.split(';').map(str::trim);
+        let name = match parts.next() {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+
+        let quality = parts
+            .filter_map(|param| param.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+            .map(|(_, value)| value.trim().parse::<f32>().unwrap_or(0.0).clamp(0.0, 1.0))
+            .unwrap_or(1.0);
+
+        if name == "*" {
+            wildcard = Some(quality);
+        } else if normalize(name).eq_ignore_ascii_case(normalize(coding)) {
+            return quality;
+        }
+    }
+
+    match wildcard {
+        Some(quality) => quality,
+        None if is_identity => 1.0,
+        None => 0.0,
+    }
+}
+
+fn normalize(coding: &str) -> &str {
+    if coding.eq_ignore_ascii_case("x-gzip") {
+        "gzip"
+    } else {
+        coding
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Debug, Display},
+    ops::Index,
+};
+
+/// # HeaderMap
+///
+/// The header fields of a request or response
+///
+/// Names are compared ignoring their case, but are sent with the case they were inserted with.
+/// A name can have multiple values, e.g. several `Set-Cookie` headers, and fields are iterated in the order they were added.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::http::HeaderMap;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert("Content-Type", "text/html");
+/// headers.append("Set-Cookie", "theme=dark");
+/// headers.append("Set-Cookie", "lang=en");
+///
+/// assert_eq!(headers.get("content-type"), Some("text/html"));
+/// assert_eq!(headers.get_all("set-cookie").collect::<Vec<_>>(), vec!["theme=dark", "lang=en"]);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+/// # HeaderError
+///
+/// Returned when a header name or value can not be sent in a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    /// The name is empty or contains characters other than letters, digits and ``!#$%&'*+-.^_`|~``
+    InvalidName(String),
+    /// The value contains a line break or another control character
+    InvalidValue(String),
+}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::InvalidName(name) => write!(f, "invalid header name '{}'", name),
+            HeaderError::InvalidValue(value) => write!(f, "invalid header value '{}'", value.escape_debug()),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self { entries: Vec::new() }
+    }
+
+    /// # HeaderMap::get
+    ///
+    /// Returns the first value of a header, ignoring the case of its name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// # HeaderMap::get_all
+    ///
+    /// Returns every value of a header in the order they were added, ignoring the case of its name
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// # HeaderMap::insert
+    ///
+    /// Sets a header, replacing all existing values for the same name
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the name or value is invalid, use `HeaderMap::try_insert` to handle this instead
+    pub fn insert(&mut self, name: &str, value: &str) {
+        if let Err(err) = self.try_insert(name, value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// # HeaderMap::append
+    ///
+    /// Adds a value to a header, keeping the existing values for the same name
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the name or value is invalid, use `HeaderMap::try_append` to handle this instead
+    pub fn append(&mut self, name: &str, value: &str) {
+        if let Err(err) = self.try_append(name, value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// # HeaderMap::try_insert
+    ///
+    /// Sets a header, replacing all existing values for the same name, or returns an error if the name or value is invalid
+    ///
+    /// The header keeps the position of its first value.
+    pub fn try_insert(&mut self, name: &str, value: &str) -> Result<(), HeaderError> {
+        validate(name, value)?;
+
+        let mut replaced = false;
+        self.entries.retain_mut(|(key, existing)| {
+            if !key.eq_ignore_ascii_case(name) {
+                return true;
+            }
+            if replaced {
+                return false;
+            }
+            *key = name.to_string();
+            *existing = value.to_string();
+            replaced = true;
+            true
+        });
+
+        if !replaced {
+            self.entries.push((name.to_string(), value.to_string()));
+        }
+        Ok(())
+    }
+
+    /// # HeaderMap::try_append
+    ///
+    /// Adds a value to a header, or returns an error if the name or value is invalid
+    pub fn try_append(&mut self, name: &str, value: &str) -> Result<(), HeaderError> {
+        validate(name, value)?;
+        self.entries.push((name.to_string(), value.to_string()));
+        Ok(())
+    }
+
+    /// # HeaderMap::remove
+    ///
+    /// Removes all values of a header and returns the first of them
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(key, _)| key.eq_ignore_ascii_case(name))?;
+        let (_, value) = self.entries.remove(index);
+        self.entries.retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        Some(value)
+    }
+
+    /// # HeaderMap::iter
+    ///
+    /// Iterates over all header fields in the order they were added, with one item per value
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The number of header fields, counting every value of a name
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Checks that a name is a token and a value contains no control characters other than tabs, see RFC 9110 section 5
+fn validate(name: &str, value: &str) -> Result<(), HeaderError> {
+    let is_token = |byte: u8| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte);
+    if name.is_empty() || !name.bytes().all(is_token) {
+        return Err(HeaderError::InvalidName(name.to_string()));
+    }
+
+    if value.chars().any(|char| char.is_ascii_control() && char != '\t') {
+        return Err(HeaderError::InvalidValue(value.to_string()));
+    }
+    Ok(())
+}
+
+/// Returns the first value of a header
+///
+/// ## Panics
+///
+/// This panics if the header is not present
+impl Index<&str> for HeaderMap {
+    type Output = String;
+
+    fn index(&self, name: &str) -> &String {
+        match self.entries.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            Some((_, value)) => value,
+            None => panic!("header '{}' is not present", name),
+        }
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for HeaderMap {
+    /// Collects header fields, appending repeated names
+    ///
+    /// ## Panics
+    ///
+    /// This panics if a name or value is invalid
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = HeaderMap::new();
+        for (key, value) in iter {
+            headers.append(key.as_ref(), value.as_ref());
+        }
+        headers
+    }
+}
+
+impl From<HashMap<String, String>> for HeaderMap {
+    fn from(headers: HashMap<String, String>) -> Self {
+        headers.into_iter().collect()
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
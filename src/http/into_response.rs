@@ -0,0 +1,98 @@
+use super::{HTTPResponse, HTTPStatusCode, ResponseBuilder};
+
+/// # IntoResponse
+///
+/// Converts the return value of a listener into the response sent to the client
+///
+/// ## Example
+///
+/// ```rs
+/// handler.set_listener("GET", "/hello", |_: HTTPRequest| "Hello World!");
+/// handler.set_listener("POST", "/users", |_: HTTPRequest| (HTTPStatusCode::Created, [("Location", "/users/1")], "Created"));
+/// handler.set_listener("GET", "/users/:id", |request: HTTPRequest| {
+///     let id = request.param("id")?.parse::<u32>().ok()?;
+///     Some(format!("User {}", id)) // None is answered with 404 Not Found
+/// });
+/// ```
+pub trait IntoResponse {
+    fn into_response(self) -> HTTPResponse;
+}
+
+impl IntoResponse for HTTPResponse {
+    fn into_response(self) -> HTTPResponse {
+        self
+    }
+}
+
+impl IntoResponse for ResponseBuilder {
+    fn into_response(self) -> HTTPResponse {
+        self.build()
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> HTTPResponse {
+        HTTPResponse::text(self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> HTTPResponse {
+        HTTPResponse::text(self)
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> HTTPResponse {
+        HTTPResponse::builder()
+            .header("Content-Type", "application/octet-stream")
+            .body(self)
+            .build()
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for (HTTPStatusCode, T) {
+    fn into_response(self) -> HTTPResponse {
+        let (status, response) = self;
+        let mut response = response.into_response();
+        response.set_status(status);
+        response
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>, const N: usize, T: IntoResponse> IntoResponse for ([(K, V); N], T) {
+    fn into_response(self) -> HTTPResponse {
+        let (headers, response) = self;
+        let mut response = response.into_response();
+        for (key, value) in headers {
+            response.set_header(key.as_ref(), value.as_ref());
+        }
+        response
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>, const N: usize, T: IntoResponse> IntoResponse for (HTTPStatusCode, [(K, V); N], T) {
+    fn into_response(self) -> HTTPResponse {
+        let (status, headers, response) = self;
+        (status, (headers, response)).into_response()
+    }
+}
+
+/// `None` is answered with the default 404 page
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    fn into_response(self) -> HTTPResponse {
+        match self {
+            Some(response) => response.into_response(),
+            None => HTTPResponse::not_found(),
+        }
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> HTTPResponse {
+        match self {
+            Ok(response) => response.into_response(),
+            Err(err) => err.into_response(),
+        }
+    }
+}
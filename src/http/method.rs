@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum HTTPMethod {
     Get,
     Head,
@@ -23,4 +25,20 @@ impl From<&str> for HTTPMethod {
             _ => HTTPMethod::Other(source.to_string()),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Display for HTTPMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let method = match self {
+            HTTPMethod::Get => "GET",
+            HTTPMethod::Head => "HEAD",
+            HTTPMethod::Post => "POST",
+            HTTPMethod::Put => "PUT",
+            HTTPMethod::Delete => "DELETE",
+            HTTPMethod::Connect => "CONNECT",
+            HTTPMethod::Options => "OPTIONS",
+            HTTPMethod::Other(method) => method,
+        };
+        write!(f, "{}", method)
+    }
+}
@@ -1,113 +1,267 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, error::Error, fmt::Display, io::{BufRead, Read}, str::FromStr, sync::Arc};
 
-use super::HTTPMethod;
+use crate::state::State;
+
+use super::{chunked, url, HTTPMethod, HTTPStatusCode, HeaderMap};
+
+/// The maximum size of the request line and headers together
+pub(super) const MAX_HEAD_SIZE: usize = 64 * 1024;
 
 #[derive(Debug)]
 pub struct HTTPRequest {
     method: HTTPMethod,
     version: String,
     uri: String,
-    headers: HashMap<String, String>,
-    body: Option<String>,
+    path: String,
+    /// The path with its segments decoded separately, see `url::decode_segments`
+    route_path: String,
+    query: Vec<(String, String)>,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+    trailers: HeaderMap,
+    params: HashMap<String, String>,
+    state: Option<Arc<State>>,
+}
+
+/// # ParseError
+///
+/// Returned when a request can not be read, together with the status code the server should answer with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    status: HTTPStatusCode,
+    message: String,
+}
+
+impl ParseError {
+    pub fn new(status: HTTPStatusCode, message: &str) -> Self {
+        Self {
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn get_status(&self) -> &HTTPStatusCode {
+        &self.status
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
 }
 
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        Self {
+            status: HTTPStatusCode::BadRequest,
+            message,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for ParseError {}
+
 impl HTTPRequest {
-    pub fn parse<T: Into<String>>(raw: impl IntoIterator<Item = T>) -> Result<HTTPRequest, String> {
-        let mut raw = raw.into_iter();
+    /// # HTTPRequest::parse
+    ///
+    /// Parses a request from its individual lines, which are joined with `\r\n`
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// let request = HTTPRequest::parse(vec!["GET / HTTP/1.1", "Host: localhost:8000"])?;
+    /// ```
+    pub fn parse<T: Into<String>>(raw: impl IntoIterator<Item = T>) -> Result<HTTPRequest, ParseError> {
+        let raw = raw
+            .into_iter()
+            .map(|line| line.into() + "\r\n")
+            .collect::<String>();
+
+        Self::read(&mut raw.as_bytes(), usize::MAX)
+    }
+
+    /// # HTTPRequest::read
+    ///
+    /// Reads a single request from a stream, leaving anything after it unread
+    ///
+    /// The body is read byte for byte based on the `Content-Length` header.
+    ///
+    /// ## Arguments
+    ///
+    /// * `reader` - The stream to read from
+    /// * `max_body_size` - The largest body that is accepted, larger bodies fail with `413 Payload Too Large`
+    pub fn read<R: BufRead>(reader: &mut R, max_body_size: usize) -> Result<HTTPRequest, ParseError> {
+        let mut remaining = MAX_HEAD_SIZE;
 
-        // Extract the meta data in the first line
-        let meta: String = match raw.next() {
-            Some(meta) => meta.into(),
-            None => return Err(String::from("Invalid formatting")),
+        // Extract the meta data in the first line, skipping empty lines left over from a previous request
+        let meta = loop {
+            match read_line(reader, &mut remaining)? {
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Err(String::from("Invalid formatting").into()),
+            }
         };
 
         let mut meta = meta.split_whitespace();
-
         let method = match meta.next() {
             Some(method) => method.into(),
-            None => return Err(String::from("Invalid formatting")),
+            None => return Err(String::from("Invalid formatting").into()),
         };
         let uri = match meta.next() {
             Some(uri) => uri.to_string(),
-            None => return Err(String::from("Invalid formatting")),
+            None => return Err(String::from("Invalid formatting").into()),
+        };
+        // Only visible ASCII is allowed in the request target, see RFC 9112 section 3.2
+        if !uri.bytes().all(|byte| (0x21..=0x7E).contains(&byte)) {
+            return Err(String::from("Invalid request target").into());
+        }
+        // Absolute-form targets (`http://host/path`) are handled by their path
+        let target = match uri.split_once("://") {
+            Some((_, rest)) if !uri.starts_with('/') => match rest.find(['/', '?']) {
+                Some(start) if rest[start..].starts_with('/') => rest[start..].to_string(),
+                Some(start) => format!("/{}", &rest[start..]),
+                None => String::from("/"),
+            },
+            _ => uri.clone(),
         };
+
+        // Split the request target into the path and the query string
+        let (raw_path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, url::parse_query(query)?),
+            None => (target.as_str(), Vec::new()),
+        };
+        let path = url::percent_decode(raw_path)?;
+        let route_path = url::decode_segments(raw_path)?;
+        if !path.starts_with('/') && path != "*" {
+            return Err(String::from("Invalid request target").into());
+        }
+
         let version = match meta.next() {
             Some(version) => {
                 if !version.starts_with("HTTP") {
-                    return Err(String::from("Invalid request type"));
+                    return Err(String::from("Invalid request type").into());
                 }
 
                 let mut split = version.split("/");
                 match split.nth(1) {
                     Some(version) => version.to_string(),
-                    None => return Err(String::from("Invalid formatting")),
+                    None => return Err(String::from("Invalid formatting").into()),
                 }
             }
-            None => return Err(String::from("Invalid formatting")),
+            None => return Err(String::from("Invalid formatting").into()),
         };
 
         // Extract the headers
-        let mut headers = HashMap::new();
+        let mut headers = HeaderMap::new();
 
-        while let Some(header) = raw.next() {
-            let header: String = header.into();
+        while let Some(header) = read_line(reader, &mut remaining)? {
             if header.is_empty() {
                 break;
             }
 
-            // Split the row into key-value pairs
-            let mut split = header.split(":").map(|s| s.trim());
+            let (key, value) = parse_header_line(&header)?;
+            headers
+                .try_append(&key, &value)
+                .map_err(|err| err.to_string())?;
+        }
+
+        // Repeated Transfer-Encoding lines form a single list of codings
+        let transfer_encoding = headers
+            .contains_key("Transfer-Encoding")
+            .then(|| headers.get_all("Transfer-Encoding").collect::<Vec<_>>().join(","));
+        let content_length = content_length(&headers)?;
 
-            let key = match split.next() {
-                Some(key) => key.to_string(),
-                None => return Err(String::from("Invalid formatting")),
-            };
+        let mut trailers = HeaderMap::new();
 
-            let mut value = split.fold(String::new(), |acc, v| {
-                acc + v + ":"
-            });
-            
-            if value.is_empty() {
-                return Err(String::from("Invalid formatting"));
+        // If the headers contains a Transfer-Encoding header then decode the chunked body,
+        // if they contain a Content-Length header then read exactly that many bytes as the body
+        let body = match (transfer_encoding, content_length) {
+            // A message with both headers could be framed differently by a proxy in front of us, see RFC 9112 section 6.1
+            (Some(_), Some(_)) => {
+                return Err(String::from("Content-Length and Transfer-Encoding must not be used together").into());
             }
-            value.pop();
+            (Some(transfer_encoding), None) => {
+                let codings = transfer_encoding
+                    .split(',')
+                    .map(|coding| coding.trim().to_ascii_lowercase())
+                    .collect::<Vec<_>>();
 
-            headers.insert(key, value);
-        }
+                if codings.last().map(String::as_str) != Some("chunked") {
+                    return Err(String::from("Request body length can not be determined").into());
+                }
+                if codings.len() > 1 {
+                    return Err(ParseError::new(HTTPStatusCode::NotImplemented, "Only the chunked transfer coding is supported"));
+                }
 
-        // If the headers contains Content-Length or Transfer-Encoding headers then get the request body
-        let body = if headers.contains_key("Content-Length")
-            || headers.contains_key("Transfer-Encoding")
-        {
-            let mut body = String::new();
-            for line in raw
-                .map(|l| {
-                    let l: String = l.into();
-                    l
-                })
-                .take_while(|l| !l.is_empty())
-            {
-                body.push_str(&format!("{}\n", line));
+                let (body, chunked_trailers) = chunked::decode(reader, max_body_size)?;
+                trailers = chunked_trailers;
+                Some(body)
             }
+            (None, Some(length)) if length > max_body_size => {
+                return Err(ParseError::new(HTTPStatusCode::PayloadTooLarge, "Request body is too large"));
+            }
+            (None, Some(length)) => {
+                let mut body = Vec::new();
+                reader
+                    .take(length as u64)
+                    .read_to_end(&mut body)
+                    .map_err(|_| String::from("Incomplete body"))?;
 
-            Some(body)
-        } else {
-            None
+                if body.len() < length {
+                    return Err(String::from("Incomplete body").into());
+                }
+                Some(body)
+            }
+            (None, None) => None,
         };
 
         Ok(HTTPRequest {
             method,
             uri,
+            path,
+            route_path,
+            query,
             version,
             headers,
             body,
+            trailers,
+            params: HashMap::new(),
+            state: None,
         })
     }
 
-    pub fn get_headers(&self) -> &HashMap<String, String> {
+    pub fn get_headers(&self) -> &HeaderMap {
         &self.headers
     }
 
+    /// # HTTPRequest::get_header
+    ///
+    /// Returns the first value of a header, ignoring the case of its name
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// # HTTPRequest::wants_keep_alive
+    ///
+    /// Returns whether the client wants to keep the connection open after this request.
+    ///
+    /// HTTP/1.1 connections are persistent unless the client sends `Connection: close`,
+    /// HTTP/1.0 connections only if the client sends `Connection: keep-alive`
+    pub fn wants_keep_alive(&self) -> bool {
+        let connection = self.get_header("Connection").unwrap_or_default();
+        let has_option = |option: &str| connection.split(',').any(|value| value.trim().eq_ignore_ascii_case(option));
+
+        match self.version.as_str() {
+            "1.0" | "0.9" => has_option("keep-alive"),
+            _ => !has_option("close"),
+        }
+    }
+
     pub fn get_version(&self) -> &str {
         &self.version
     }
@@ -116,11 +270,202 @@ impl HTTPRequest {
         &self.method
     }
 
+    /// # HTTPRequest::get_uri
+    ///
+    /// Returns the raw request target, including the query string
     pub fn get_uri(&self) -> &str {
         &self.uri
     }
 
-    pub fn get_body(&self) -> &Option<String> {
+    /// # HTTPRequest::get_path
+    ///
+    /// Returns the percent-decoded path of the request target, without the query string
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// The path listeners are matched against, in which `/` and `%` inside of a segment are still encoded
+    pub(crate) fn get_route_path(&self) -> &str {
+        &self.route_path
+    }
+
+    /// # HTTPRequest::get_query
+    ///
+    /// Returns all percent-decoded query parameters in the order they appear in the request target
+    pub fn get_query(&self) -> &[(String, String)] {
+        &self.query
+    }
+
+    /// # HTTPRequest::query
+    ///
+    /// Returns the first value of a query parameter
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// // GET /search?q=carola
+    /// assert_eq!(request.query("q"), Some("carola"));
+    /// ```
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// # HTTPRequest::query_all
+    ///
+    /// Returns all values of a repeated query parameter, e.g. `?tag=a&tag=b`
+    pub fn query_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.query
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// # HTTPRequest::query_as
+    ///
+    /// Parses the first value of a query parameter into the given type
+    ///
+    /// Returns `None` if the parameter is missing
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// // GET /items?page=2
+    /// let page: u32 = request.query_as("page").unwrap_or(Ok(1))?;
+    /// ```
+    pub fn query_as<F: FromStr>(&self, key: &str) -> Option<Result<F, F::Err>> {
+        self.query(key).map(str::parse)
+    }
+
+    pub fn get_body(&self) -> &Option<Vec<u8>> {
         &self.body
     }
+
+    /// # HTTPRequest::get_trailers
+    ///
+    /// Returns the trailer fields sent after a chunked body
+    pub fn get_trailers(&self) -> &HeaderMap {
+        &self.trailers
+    }
+
+    /// # HTTPRequest::get_body_str
+    ///
+    /// Returns the body as text, or `None` if there is no body or it is not valid UTF-8
+    pub fn get_body_str(&self) -> Option<&str> {
+        self.body.as_deref().and_then(|body| std::str::from_utf8(body).ok())
+    }
+
+    pub fn get_params(&self) -> &HashMap<String, String> {
+        &self.params
+    }
+
+    /// # HTTPRequest::param
+    ///
+    /// Returns the percent-decoded value captured by a `:name` or `*name` segment of the matched listener path.
+    /// Segments are decoded after matching, so `/users/a%2Fb` matches `/users/:id` with `a/b` as the id
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// handler.set_listener("GET", "/users/:id", |request: HTTPRequest| {
+    ///     let id = request.param("id").unwrap();
+    ///     // ...
+    /// });
+    /// ```
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(|value| value.as_str())
+    }
+
+    /// # HTTPRequest::state
+    ///
+    /// Returns the value of the given type stored with `RequestHandler::set_state`
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// handler.set_state(AtomicUsize::new(0));
+    /// handler.set_listener("GET", "/visits", |request: HTTPRequest| {
+    ///     let visits = request.state::<AtomicUsize>().unwrap().fetch_add(1, Ordering::Relaxed);
+    ///     // ...
+    /// });
+    /// ```
+    pub fn state<T: std::any::Any + Send + Sync>(&self) -> Option<&T> {
+        self.state.as_ref().and_then(|state| state.get())
+    }
+
+    pub(crate) fn set_state(&mut self, state: Arc<State>) {
+        self.state = Some(state);
+    }
+
+    pub(crate) fn set_params(&mut self, params: HashMap<String, String>) {
+        self.params = params;
+    }
+}
+
+/// Splits a header line into its name and value
+pub(super) fn parse_header_line(line: &str) -> Result<(String, String), ParseError> {
+    let (key, value) = match line.split_once(':') {
+        Some(split) => split,
+        None => return Err(String::from("Invalid formatting").into()),
+    };
+
+    // Whitespace around the name is rejected rather than trimmed, see RFC 9112 section 5.1
+    if key.is_empty() || key.starts_with([' ', '\t']) || key.ends_with([' ', '\t']) {
+        return Err(String::from("Invalid formatting").into());
+    }
+
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// Reads a line terminated by `\n` and strips the line ending, returning `None` at the end of the stream
+pub(super) fn read_line<R: BufRead>(reader: &mut R, remaining: &mut usize) -> Result<Option<String>, ParseError> {
+    let mut line = Vec::new();
+    let read = reader
+        .take(*remaining as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .map_err(|_| String::from("Invalid formatting"))?;
+
+    if read == 0 {
+        return Ok(None);
+    }
+    if read > *remaining {
+        return Err(ParseError::new(HTTPStatusCode::RequestHeaderFieldsTooLarge, "Request headers are too large"));
+    }
+    *remaining -= read;
+
+    if line.ends_with(b"\n") {
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| String::from("Invalid formatting").into())
+}
+
+/// Returns the value of the Content-Length header, rejecting malformed or conflicting values
+fn content_length(headers: &HeaderMap) -> Result<Option<usize>, ParseError> {
+    let mut length = None;
+
+    for value in headers.get_all("Content-Length") {
+        for value in value.split(',').map(|value| value.trim()) {
+            if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(String::from("Invalid Content-Length").into());
+            }
+            let value = value
+                .parse::<usize>()
+                .map_err(|_| ParseError::new(HTTPStatusCode::PayloadTooLarge, "Request body is too large"))?;
+
+            match length {
+                Some(length) if length != value => return Err(String::from("Invalid Content-Length").into()),
+                _ => length = Some(value),
+            }
+        }
+    }
+
+    Ok(length)
 }
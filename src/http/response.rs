@@ -1,70 +1,475 @@
-use std::collections::HashMap;
+use std::io::{self, Write};
 
-use super::HTTPStatusCode;
+use super::{
+    body::Framing,
+    compression::{Coding, Encoder},
+    BodyStream, HTTPStatusCode, HeaderMap,
+};
 
+/// # HTTPResponse
+///
+/// A response sent to the client, created with `HTTPResponse::builder`, one of the convenience constructors or `HTTPResponse::new`
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::http::{HTTPResponse, HTTPStatusCode};
+///
+/// HTTPResponse::text("Hello World!");
+/// HTTPResponse::builder()
+///     .status(HTTPStatusCode::Created)
+///     .header("Location", "/users/1")
+///     .json(r#"{"id":1}"#);
+/// ```
+#[derive(Debug)]
 pub struct HTTPResponse {
-    version: String,
+    version: Option<String>,
     status: HTTPStatusCode,
-    headers: HashMap<String, String>,
-    body: Option<String>,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+    stream: Option<BodyStream>,
+    chunked: bool,
 }
 
 impl HTTPResponse {
-    pub fn new(
+    pub fn new<H: Into<HeaderMap>>(
         version: &str,
         status: HTTPStatusCode,
-        headers: HashMap<String, String>,
-        body: Option<String>,
+        headers: H,
+        body: Option<Vec<u8>>,
     ) -> HTTPResponse {
         Self {
-            version: version.to_owned(),
+            version: Some(version.to_owned()),
             status,
-            headers,
+            headers: headers.into(),
             body,
+            stream: None,
+            chunked: true,
         }
     }
 
+    /// # HTTPResponse::new_streaming
+    /// 
+    /// Creates a response whose body is streamed to the client instead of being held in memory
+    /// 
+    /// ## Example
+    /// 
+    /// ```rs
+    /// let file = File::open("export.csv")?;
+    /// HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HeaderMap::new(), BodyStream::from_file(file)?)
+    /// ```
+    pub fn new_streaming<H: Into<HeaderMap>>(
+        version: &str,
+        status: HTTPStatusCode,
+        headers: H,
+        stream: BodyStream,
+    ) -> HTTPResponse {
+        Self {
+            version: Some(version.to_owned()),
+            status,
+            headers: headers.into(),
+            body: None,
+            stream: Some(stream),
+            chunked: true,
+        }
+    }
+
+    /// # HTTPResponse::builder
+    ///
+    /// Creates a builder for a `200 OK` response without headers or body
+    ///
+    /// Unless a version is set on the builder, the response uses the version of the request it answers.
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::new()
+    }
+
+    /// # HTTPResponse::text
+    ///
+    /// Creates a `200 OK` response with a plain text body
+    pub fn text<B: Into<String>>(body: B) -> HTTPResponse {
+        Self::builder().text(body)
+    }
+
+    /// # HTTPResponse::html
+    ///
+    /// Creates a `200 OK` response with an html body
+    pub fn html<B: Into<String>>(body: B) -> HTTPResponse {
+        Self::builder().html(body)
+    }
+
+    /// # HTTPResponse::json
+    ///
+    /// Creates a `200 OK` response with a body that is already serialized to json
+    pub fn json<B: Into<String>>(body: B) -> HTTPResponse {
+        Self::builder().json(body)
+    }
+
+    /// # HTTPResponse::redirect
+    ///
+    /// Creates a `302 Found` response redirecting the client to the given location
+    ///
+    /// Other redirects can be built with `HTTPResponse::builder().status(..).header("Location", ..)`
+    pub fn redirect(location: &str) -> HTTPResponse {
+        Self::builder()
+            .status(HTTPStatusCode::Found)
+            .header("Location", location)
+            .build()
+    }
+
+    /// # HTTPResponse::no_content
+    ///
+    /// Creates a `204 No Content` response
+    pub fn no_content() -> HTTPResponse {
+        Self::builder().status(HTTPStatusCode::NoContent).build()
+    }
+
+    /// # HTTPResponse::error
+    ///
+    /// Creates a response with a simple html page describing the status, e.g. `404 Not Found`
+    pub fn error(status: HTTPStatusCode) -> HTTPResponse {
+        let page = include_str!("../../assets/error.html")
+            .replace("{status}", &status.value().to_string())
+            .replace("{message}", &status.message());
+
+        Self::builder().status(status).html(page)
+    }
+
+    /// The default 404 page
+    pub(crate) fn not_found() -> HTTPResponse {
+        Self::builder()
+            .status(HTTPStatusCode::NotFound)
+            .html(include_str!("../../assets/404.html"))
+    }
+
+    /// # HTTPResponse::get_version
+    ///
+    /// Returns the version the response is sent with, which is HTTP/1.1 until it has been matched to a request
+    pub fn get_version(&self) -> &str {
+        self.version.as_deref().unwrap_or("1.1")
+    }
+
+    /// Answers with the version of the request unless the response has a version of its own.
+    ///
+    /// The response is never sent with a newer version than the one of the request, nor older than HTTP/1.0
+    pub(crate) fn set_default_version(&mut self, request_version: &str) {
+        if self.version.is_none() {
+            let version = match request_version {
+                "1.0" | "0.9" => "1.0",
+                _ => "1.1",
+            };
+            self.version = Some(version.to_string());
+        }
+    }
+
+    pub fn get_status(&self) -> &HTTPStatusCode {
+        &self.status
+    }
+
+    pub fn get_headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn get_body(&self) -> &Option<Vec<u8>> {
+        &self.body
+    }
+
+    pub fn get_stream(&self) -> &Option<BodyStream> {
+        &self.stream
+    }
+
+    /// # HTTPResponse::has_body
+    /// 
+    /// Returns true if the response has a buffered or streamed body
+    pub fn has_body(&self) -> bool {
+        self.body.is_some() || self.stream.is_some()
+    }
+
+    /// # HTTPResponse::get_body_str
+    /// 
+    /// Returns the body as text, or `None` if there is no body or it is not valid UTF-8
+    pub fn get_body_str(&self) -> Option<&str> {
+        self.body.as_deref().and_then(|body| std::str::from_utf8(body).ok())
+    }
+
+    /// # HTTPResponse::get_header
+    /// 
+    /// Returns the first value of a header, ignoring the case of its name
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    /// # HTTPResponse::set_status
+    /// 
+    /// Sets the status of the response
+    pub fn set_status(&mut self, status: HTTPStatusCode) {
+        self.status = status;
+    }
+
+    /// # HTTPResponse::set_header
+    /// 
+    /// Sets a header on the response, replacing any existing values for the same key
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the key or value is not a valid header, see `HeaderMap::insert`
+    pub fn set_header(&mut self, key: &str, value: &str) {
+        self.headers.insert(key, value);
+    }
+
+    /// # HTTPResponse::append_header
+    /// 
+    /// Adds a header to the response, keeping any existing values for the same key, e.g. for multiple `Set-Cookie` headers
+    /// 
+    /// ## Panics
+    /// 
+    /// This function will panic if the key or value is not a valid header, see `HeaderMap::append`
+    pub fn append_header(&mut self, key: &str, value: &str) {
+        self.headers.append(key, value);
+    }
+
+    /// # HTTPResponse::strip_body
+    /// 
+    /// Removes the body from the response while keeping the `Content-Length` it would have had.
+    /// 
+    /// This is used to answer `HEAD` requests with the headers of the corresponding `GET` response
+    pub fn strip_body(&mut self) {
+        if let Some(body) = self.body.take() {
+            self.headers.insert("Content-Length", &body.len().to_string());
+        }
+        if let Some(length) = self.stream.take().and_then(|stream| stream.get_length()) {
+            self.headers.insert("Content-Length", &length.to_string());
+        }
+    }
+
+    /// Compresses the body with the given content coding, streamed bodies are compressed while they are written
+    pub(crate) fn encode_body(&mut self, coding: Coding) {
+        if let Some(body) = &self.body {
+            self.body = Some(Encoder::encode(coding, body));
+        }
+        if let Some(stream) = self.stream.take() {
+            self.stream = Some(stream.encode(Encoder::new(coding)));
+        }
+
+        self.headers.remove("Content-Length");
+        self.headers.insert("Content-Encoding", coding.name());
+    }
+
+    /// Stops streamed bodies of unknown length from being sent with `Transfer-Encoding: chunked`,
+    /// which HTTP/1.0 clients do not understand. The body is then sent as is and ends when the connection is closed.
+    pub(crate) fn disable_chunked(&mut self) {
+        self.chunked = false;
+    }
+
+    /// Returns true if the end of the body can only be signalled by closing the connection
+    pub(crate) fn is_close_delimited(&self) -> bool {
+        self.framing() == Some(Framing::Close)
+    }
+
+    fn framing(&self) -> Option<Framing> {
+        self.stream.as_ref().map(|stream| {
+            let length = stream
+                .get_length()
+                .or_else(|| self.get_header("Content-Length").and_then(|length| length.parse().ok()));
+            match length {
+                Some(length) => Framing::Length(length),
+                None if self.chunked => Framing::Chunked,
+                None => Framing::Close,
+            }
+        })
+    }
+
     /// # HTTPResponse::construct
     /// 
-    /// Constructs the http response into a string
+    /// Constructs the http response into the bytes sent to the client
     /// 
-    /// This automatically creates the `Content-Length` header and overides any already existing headers with the same key
-    pub fn construct(mut self) -> String {
+    /// This automatically creates the `Content-Length` header and overides any already existing headers with the same key.
+    /// Streamed bodies are read completely, if reading fails the output ends where the error occurred.
+    pub fn construct(self) -> Vec<u8> {
+        let mut response = Vec::new();
+        let _ = self.write_to(&mut response);
+        response
+    }
+
+    /// # HTTPResponse::write_to
+    /// 
+    /// Writes the http response to a stream
+    /// 
+    /// Buffered and streamed bodies of known length are sent with a `Content-Length` header,
+    /// streamed bodies of unknown length with `Transfer-Encoding: chunked`, followed by their trailers.
+    pub fn write_to<W: Write>(mut self, writer: &mut W) -> io::Result<()> {
         let status_line = format!(
             "HTTP/{} {} {}",
-            self.version,
-            self.status.value().to_string(),
+            self.get_version(),
+            self.status.value(),
             self.status.message()
         );
 
         if let Some(body) = &self.body {
-            self.headers.insert(
-                String::from("Content-Length"),
-                body.len().to_string(),
-            );
+            self.headers.insert("Content-Length", &body.len().to_string());
         }
+
+        let framing = self.framing();
+        match framing {
+            Some(Framing::Length(length)) => {
+                self.headers.insert("Content-Length", &length.to_string());
+            }
+            Some(Framing::Chunked) => {
+                self.headers.remove("Content-Length");
+                self.headers.insert("Transfer-Encoding", "chunked");
+
+                let trailers = self.stream.as_ref().map(|stream| stream.get_trailer_names().join(", "));
+                if let Some(trailers) = trailers.filter(|trailers| !trailers.is_empty()) {
+                    self.headers.insert("Trailer", &trailers);
+                }
+            }
+            Some(Framing::Close) => {
+                self.headers.remove("Content-Length");
+                self.headers.remove("Transfer-Encoding");
+                self.headers.insert("Connection", "close");
+            }
+            None => {}
+        }
+
         let headers = self
             .headers
             .into_iter()
             .map(|(key, value)| format!("{}:{}", key, value))
             .collect::<Vec<_>>();
 
-        let rows: Vec<String>;
+        let rows = [vec![status_line], headers].into_iter().flatten().collect::<Vec<_>>();
+        writer.write_all((rows.join("\r\n") + "\r\n\r\n").as_bytes())?;
 
+        // Nothing may follow the body, the connection could be reused for the next response
         if let Some(body) = self.body {
-            rows = [
-                vec![status_line],
-                headers,
-                vec![String::from("")],
-                vec![body],
-            ]
-            .into_iter()
-            .flatten()
-            .collect();
-        } else {
-            rows = [vec![status_line], headers].into_iter().flatten().collect();
+            writer.write_all(&body)?;
         }
+        if let (Some(stream), Some(framing)) = (self.stream, framing) {
+            stream.write_to(writer, framing)?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// # ResponseBuilder
+///
+/// Builds an `HTTPResponse` step by step, created with `HTTPResponse::builder`
+///
+/// ## Example
+///
+/// ```rs
+/// HTTPResponse::builder()
+///     .status(HTTPStatusCode::Created)
+///     .header("Location", "/users/1")
+///     .body("Created")
+///     .build()
+/// ```
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    response: HTTPResponse,
+}
+
+impl ResponseBuilder {
+    fn new() -> Self {
+        Self {
+            response: HTTPResponse {
+                version: None,
+                status: HTTPStatusCode::OK,
+                headers: HeaderMap::new(),
+                body: None,
+                stream: None,
+                chunked: true,
+            },
+        }
+    }
+
+    /// # ResponseBuilder::version
+    ///
+    /// Sets the version of the response instead of using the version of the request
+    pub fn version(mut self, version: &str) -> Self {
+        self.response.version = Some(version.to_string());
+        self
+    }
+
+    /// # ResponseBuilder::status
+    ///
+    /// Sets the status of the response, `200 OK` by default
+    pub fn status(mut self, status: HTTPStatusCode) -> Self {
+        self.response.status = status;
+        self
+    }
+
+    /// # ResponseBuilder::header
+    ///
+    /// Sets a header, replacing any existing values for the same key
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the key or value is not a valid header, see `HeaderMap::insert`
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.response.set_header(key, value);
+        self
+    }
+
+    /// # ResponseBuilder::append_header
+    ///
+    /// Adds a header, keeping any existing values for the same key
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the key or value is not a valid header, see `HeaderMap::append`
+    pub fn append_header(mut self, key: &str, value: &str) -> Self {
+        self.response.append_header(key, value);
+        self
+    }
+
+    /// # ResponseBuilder::body
+    ///
+    /// Sets the body of the response, replacing a previously set body or stream
+    pub fn body<B: Into<Vec<u8>>>(mut self, body: B) -> Self {
+        self.response.body = Some(body.into());
+        self.response.stream = None;
+        self
+    }
+
+    /// # ResponseBuilder::stream
+    ///
+    /// Streams the body of the response, replacing a previously set body or stream
+    pub fn stream(mut self, stream: BodyStream) -> Self {
+        self.response.stream = Some(stream);
+        self.response.body = None;
+        self
+    }
+
+    /// # ResponseBuilder::text
+    ///
+    /// Builds the response with a plain text body
+    pub fn text<B: Into<String>>(self, body: B) -> HTTPResponse {
+        self.content("text/plain; charset=utf-8", body.into())
+    }
+
+    /// # ResponseBuilder::html
+    ///
+    /// Builds the response with an html body
+    pub fn html<B: Into<String>>(self, body: B) -> HTTPResponse {
+        self.content("text/html; charset=utf-8", body.into())
+    }
+
+    /// # ResponseBuilder::json
+    ///
+    /// Builds the response with a body that is already serialized to json
+    pub fn json<B: Into<String>>(self, body: B) -> HTTPResponse {
+        self.content("application/json", body.into())
+    }
+
+    /// # ResponseBuilder::build
+    ///
+    /// Builds the response
+    pub fn build(self) -> HTTPResponse {
+        self.response
+    }
 
-        rows.join("\r\n") + "\r\n\r\n"
+    fn content(self, content_type: &str, body: String) -> HTTPResponse {
+        self.header("Content-Type", content_type).body(body).build()
     }
 }
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HTTPStatusCode {
     Continue = 100,
     SwitchingProtocols = 101,
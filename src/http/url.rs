@@ -0,0 +1,74 @@
+/// # url::percent_decode
+///
+/// Decodes `%XX` escape sequences in a path or query component.
+///
+/// Returns an error if an escape sequence is malformed or the decoded bytes are not valid UTF-8
+pub fn percent_decode(source: &str) -> Result<String, String> {
+    let mut bytes = Vec::with_capacity(source.len());
+    let mut raw = source.bytes();
+
+    while let Some(byte) = raw.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let high = raw.next().and_then(hex_value);
+        let low = raw.next().and_then(hex_value);
+        match (high, low) {
+            (Some(high), Some(low)) => bytes.push(high << 4 | low),
+            _ => return Err(String::from("Invalid percent-encoding")),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| String::from("Invalid percent-encoding"))
+}
+
+/// Decodes every segment of a path on its own, escaping `/` and `%` again where they were part of a segment
+///
+/// Routes are matched against this path, so that an encoded slash does not split a segment in two
+pub(crate) fn decode_segments(path: &str) -> Result<String, String> {
+    let segments = path
+        .split('/')
+        .map(|segment| Ok(percent_decode(segment)?.replace('%', "%25").replace('/', "%2F")))
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(segments.join("/"))
+}
+
+/// # url::parse_query
+///
+/// Parses an `application/x-www-form-urlencoded` query string into its key-value pairs, keeping their order.
+///
+/// `+` is decoded as a space and keys without a value get an empty value
+pub fn parse_query(query: &str) -> Result<Vec<(String, String)>, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((
+                percent_decode(&key.replace('+', " "))?,
+                percent_decode(&value.replace('+', " "))?,
+            ))
+        })
+        .collect()
+}
+
+/// # url::percent_encode
+///
+/// Encodes a single path segment, escaping every byte except unreserved characters (`A-Z a-z 0-9 - . _ ~`)
+pub fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
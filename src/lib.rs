@@ -1,7 +1,10 @@
 pub mod handler;
 pub mod http;
+pub mod mime;
+pub mod public_folder;
+pub mod router;
+pub mod state;
 
-mod file;
 mod thread_pool;
 
 #[cfg(feature = "slim")]
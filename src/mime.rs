@@ -0,0 +1,398 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+};
+
+/// The type of files whose type is unknown
+const FALLBACK: &str = "application/octet-stream";
+
+/// How many bytes of a file are read to sniff its type
+const SNIFF_LENGTH: usize = 512;
+
+/// The extensions known by default, mostly following the types registered with IANA
+const DEFAULT_TYPES: &[(&str, &str)] = &[
+    // Text
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("shtml", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("cjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("text", "text/plain"),
+    ("log", "text/plain"),
+    ("conf", "text/plain"),
+    ("ini", "text/plain"),
+    ("md", "text/markdown"),
+    ("markdown", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("ics", "text/calendar"),
+    ("vcf", "text/vcard"),
+    ("vtt", "text/vtt"),
+    ("srt", "text/plain"),
+    ("rtf", "text/rtf"),
+    ("yaml", "text/yaml"),
+    ("yml", "text/yaml"),
+    ("toml", "text/plain"),
+    ("rs", "text/plain"),
+    ("appcache", "text/cache-manifest"),
+    // Structured data
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("jsonld", "application/ld+json"),
+    ("geojson", "application/geo+json"),
+    ("webmanifest", "application/manifest+json"),
+    ("xml", "application/xml"),
+    ("xsl", "application/xml"),
+    ("xsd", "application/xml"),
+    ("dtd", "application/xml-dtd"),
+    ("xhtml", "application/xhtml+xml"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("wasm", "application/wasm"),
+    // Images
+    ("png", "image/png"),
+    ("apng", "image/apng"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("jpe", "image/jpeg"),
+    ("jfif", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("svg", "image/svg+xml"),
+    // Gzipped svg, public folders send it with `Content-Encoding: gzip`
+    ("svgz", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("cur", "image/x-icon"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    ("heic", "image/heic"),
+    ("heif", "image/heif"),
+    ("jxl", "image/jxl"),
+    ("psd", "image/vnd.adobe.photoshop"),
+    // Audio
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("aac", "audio/aac"),
+    ("m4a", "audio/mp4"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    ("weba", "audio/webm"),
+    // Video
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mkv", "video/x-matroska"),
+    ("mpeg", "video/mpeg"),
+    ("mpg", "video/mpeg"),
+    ("ts", "video/mp2t"),
+    ("m3u8", "application/vnd.apple.mpegurl"),
+    ("mpd", "application/dash+xml"),
+    ("3gp", "video/3gpp"),
+    ("flv", "video/x-flv"),
+    // Fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
+    // Documents
+    ("pdf", "application/pdf"),
+    ("epub", "application/epub+zip"),
+    ("doc", "application/msword"),
+    ("docx", "application/vnd.openxmlformats-officedocument.wordprocessingml.document"),
+    ("xls", "application/vnd.ms-excel"),
+    ("xlsx", "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    ("pptx", "application/vnd.openxmlformats-officedocument.presentationml.presentation"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("ps", "application/postscript"),
+    // Archives
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tgz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("bz2", "application/x-bzip2"),
+    ("xz", "application/x-xz"),
+    ("zst", "application/zstd"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("br", "application/x-brotli"),
+    // Binaries and other application data
+    ("bin", "application/octet-stream"),
+    ("exe", "application/octet-stream"),
+    ("dll", "application/octet-stream"),
+    ("iso", "application/octet-stream"),
+    ("dmg", "application/octet-stream"),
+    ("deb", "application/vnd.debian.binary-package"),
+    ("rpm", "application/x-rpm"),
+    ("apk", "application/vnd.android.package-archive"),
+    ("jar", "application/java-archive"),
+    ("sh", "application/x-sh"),
+    ("sql", "application/sql"),
+    ("swf", "application/x-shockwave-flash"),
+    ("torrent", "application/x-bittorrent"),
+    ("pem", "application/x-pem-file"),
+    ("crt", "application/x-x509-ca-cert"),
+];
+
+/// # MimeRegistry
+///
+/// Maps file extensions to the `Content-Type` files are served with, built once and shared by all requests
+///
+/// The registry starts with a table of the common types used on the web, which can be extended or overridden
+/// with single types or with files in the format of `/etc/mime.types`. Extensions are compared case-insensitively.
+///
+/// Text types are sent with `; charset=utf-8`. Files with an unknown extension are sent as `application/octet-stream`,
+/// unless sniffing is enabled, in which case the type is guessed from the first bytes of the file.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::mime::MimeRegistry;
+///
+/// let registry = MimeRegistry::new()
+///     .with_type("gltf", "model/gltf+json")
+///     .with_types_file("/etc/mime.types")?
+///     .with_sniffing(true);
+///
+/// let mut handler = RequestHandler::new();
+/// handler.set_mime_registry(registry);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MimeRegistry {
+    types: HashMap<String, String>,
+    sniffing: bool,
+}
+
+impl MimeRegistry {
+    /// # MimeRegistry::new
+    ///
+    /// Creates a registry with the default table of types and sniffing disabled
+    pub fn new() -> Self {
+        let types = DEFAULT_TYPES
+            .iter()
+            .map(|(extension, mime_type)| (extension.to_string(), mime_type.to_string()))
+            .collect();
+
+        Self { types, sniffing: false }
+    }
+
+    /// # MimeRegistry::with_type
+    ///
+    /// Adds a type for an extension, replacing the type the extension had before
+    ///
+    /// ## Arguments
+    ///
+    /// * `extension` - The extension without the leading dot, e.g. `gltf`
+    /// * `mime_type` - The type of files with the extension, e.g. `model/gltf+json`
+    pub fn with_type(mut self, extension: &str, mime_type: &str) -> Self {
+        self.insert(extension, mime_type);
+        self
+    }
+
+    /// # MimeRegistry::with_types_file
+    ///
+    /// Adds the types of a file in the format of `/etc/mime.types`, replacing the types the extensions had before
+    ///
+    /// Every line holds a type followed by its extensions, separated by whitespace. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file can not be read
+    pub fn with_types_file<P: AsRef<Path>>(mut self, path: P) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        self.add_types(&contents);
+        Ok(self)
+    }
+
+    /// # MimeRegistry::with_sniffing
+    ///
+    /// Enables guessing the type of files with an unknown extension from their first bytes, disabled by default
+    ///
+    /// Common image, audio, video, font and archive formats are recognized by their signatures,
+    /// html and xml by their beginning and other valid UTF-8 without control characters as `text/plain`.
+    pub fn with_sniffing(mut self, enabled: bool) -> Self {
+        self.sniffing = enabled;
+        self
+    }
+
+    /// # MimeRegistry::add_types
+    ///
+    /// Adds the types of the contents of a file in the format of `/etc/mime.types`, see `MimeRegistry::with_types_file`
+    pub fn add_types(&mut self, contents: &str) {
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            if let Some(mime_type) = fields.next() {
+                for extension in fields {
+                    self.insert(extension, mime_type);
+                }
+            }
+        }
+    }
+
+    /// # MimeRegistry::insert
+    ///
+    /// Adds a type for an extension, replacing the type the extension had before, see `MimeRegistry::with_type`
+    pub fn insert(&mut self, extension: &str, mime_type: &str) {
+        let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+        self.types.insert(extension, mime_type.to_string());
+    }
+
+    /// # MimeRegistry::get
+    ///
+    /// Returns the type registered for an extension, without a charset
+    pub fn get(&self, extension: &str) -> Option<&str> {
+        self.types
+            .get(&extension.trim_start_matches('.').to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// # MimeRegistry::is_sniffing
+    ///
+    /// Returns whether the type of files without a known extension is guessed from their first bytes
+    pub fn is_sniffing(&self) -> bool {
+        self.sniffing
+    }
+
+    /// Returns all registered types, sorted and each only once
+    pub fn mime_types(&self) -> Vec<&str> {
+        let mut mime_types = self.types.values().map(String::as_str).collect::<Vec<_>>();
+        mime_types.sort_unstable();
+        mime_types.dedup();
+        mime_types
+    }
+
+    /// # MimeRegistry::content_type
+    ///
+    /// Returns the `Content-Type` a file is served with, including the charset of text types
+    ///
+    /// If the extension of the file is unknown and sniffing is enabled, the beginning of the file is read to guess its type.
+    pub fn content_type(&self, path: &Path) -> String {
+        let mime_type = match path.extension().and_then(|extension| self.get(&extension.to_string_lossy())) {
+            Some(mime_type) => mime_type,
+            None if self.sniffing => read_start(path).map_or(FALLBACK, |start| sniff(&start)),
+            None => FALLBACK,
+        };
+
+        with_charset(mime_type)
+    }
+}
+
+impl Default for MimeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # mime::sniff
+///
+/// Guesses the type of a file from its first bytes, returning `application/octet-stream` if it is not recognized
+pub fn sniff(start: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1A\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1F\x8B\x08", "application/gzip"),
+        (b"\xFD7zXZ\x00", "application/x-xz"),
+        (b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+        (b"\x00asm", "application/wasm"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1A\x45\xDF\xA3", "video/webm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"%!PS", "application/postscript"),
+    ];
+
+    if let Some((_, mime_type)) = SIGNATURES.iter().find(|(signature, _)| start.starts_with(signature)) {
+        return mime_type;
+    }
+
+    // Formats with a container header before the actual signature
+    if start.len() >= 12 && &start[..4] == b"RIFF" {
+        match &start[8..12] {
+            b"WEBP" => return "image/webp",
+            b"WAVE" => return "audio/wav",
+            b"AVI " => return "video/x-msvideo",
+            _ => {}
+        }
+    }
+    if start.len() >= 12 && &start[4..8] == b"ftyp" {
+        return match &start[8..12] {
+            b"avif" => "image/avif",
+            b"heic" | b"heix" => "image/heic",
+            b"qt  " => "video/quicktime",
+            b"M4A " => "audio/mp4",
+            _ => "video/mp4",
+        };
+    }
+
+    sniff_text(start)
+}
+
+fn sniff_text(start: &[u8]) -> &'static str {
+    // The last character might have been cut in half
+    let text = match std::str::from_utf8(start) {
+        Ok(text) => text,
+        Err(err) if err.error_len().is_none() => std::str::from_utf8(&start[..err.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return FALLBACK,
+    };
+    let text = text.trim_start_matches('\u{FEFF}');
+
+    if text.chars().any(|char| char.is_control() && !matches!(char, '\t' | '\n' | '\r' | '\x0C')) {
+        return FALLBACK;
+    }
+
+    let lowercase = text.trim_start().to_ascii_lowercase();
+    if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") || lowercase.starts_with("<head") || lowercase.starts_with("<body") {
+        "text/html"
+    } else if lowercase.starts_with("<svg") {
+        "image/svg+xml"
+    } else if lowercase.starts_with("<?xml") {
+        "application/xml"
+    } else if text.is_empty() {
+        FALLBACK
+    } else {
+        "text/plain"
+    }
+}
+
+fn read_start(path: &Path) -> io::Result<Vec<u8>> {
+    let mut start = Vec::with_capacity(SNIFF_LENGTH);
+    File::open(path)?.take(SNIFF_LENGTH as u64).read_to_end(&mut start)?;
+    Ok(start)
+}
+
+/// Text types are assumed to be UTF-8, unless the type already has parameters
+fn with_charset(mime_type: &str) -> String {
+    if mime_type.starts_with("text/") && !mime_type.contains(';') {
+        format!("{}; charset=utf-8", mime_type)
+    } else {
+        mime_type.to_string()
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    http::{date::format_http_date, url::percent_encode, BodyStream, HTTPMethod, HTTPRequest, HTTPResponse, HTTPStatusCode},
+    mime::MimeRegistry,
+};
+
+use self::{
+    range::RangeRequest,
+    resolve::{resolve, Resolved},
+};
+
+mod conditional;
+mod listing;
+mod precompressed;
+mod range;
+mod resolve;
+pub use resolve::{DotfilePolicy, SymlinkPolicy};
+
+/// # PublicFolder
+///
+/// A folder of static files served by a `RequestHandler`
+///
+/// A handler can serve several folders at different path prefixes, each with its own options, see `RequestHandler::mount_public_folder`.
+///
+/// Request paths are normalized before they are mapped onto the folder, so that no request can read files outside of it.
+/// By default symbolic links are only followed if they point inside the folder, and dotfiles are treated as missing.
+///
+/// Files are streamed from disk and support `Range` requests, so that clients can resume downloads and seek in media files.
+/// They are sent with an `ETag` and `Last-Modified` header, and conditional requests are answered with
+/// `304 Not Modified` or `412 Precondition Failed`.
+///
+/// Precompressed variants stored next to a file, like `app.js.br` and `app.js.gz` for `app.js`,
+/// are sent instead of the file to clients whose `Accept-Encoding` allows it.
+///
+/// Requests for a directory are redirected to the path with a trailing `/`, which serves the index file of the directory
+/// or, if enabled, a listing of its contents.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::public_folder::{DotfilePolicy, PublicFolder, SymlinkPolicy};
+///
+/// let mut handler = RequestHandler::new();
+/// handler.set_public_folder(
+///     PublicFolder::new("./public")
+///         .with_symlink_policy(SymlinkPolicy::Deny)
+///         .with_dotfile_policy(DotfilePolicy::Deny),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PublicFolder {
+    root: PathBuf,
+    symlinks: SymlinkPolicy,
+    dotfiles: DotfilePolicy,
+    index_file: Option<String>,
+    listing: bool,
+    cache_control: Option<String>,
+    spa_fallback: Option<String>,
+    spa_excludes: Vec<String>,
+}
+
+impl PublicFolder {
+    /// # PublicFolder::new
+    ///
+    /// Creates a public folder serving the files in `root`
+    ///
+    /// ## Arguments
+    ///
+    /// * `root` - The path to the folder, either an absolute path or one that is relative to the working directory
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            symlinks: SymlinkPolicy::default(),
+            dotfiles: DotfilePolicy::default(),
+            index_file: Some(String::from("index.html")),
+            listing: false,
+            cache_control: None,
+            spa_fallback: None,
+            spa_excludes: Vec::new(),
+        }
+    }
+
+    /// # PublicFolder::with_symlink_policy
+    ///
+    /// Sets whether symbolic links are followed, `SymlinkPolicy::WithinRoot` by default
+    pub fn with_symlink_policy(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// # PublicFolder::with_dotfile_policy
+    ///
+    /// Sets how paths containing dotfiles are answered, `DotfilePolicy::Ignore` by default
+    pub fn with_dotfile_policy(mut self, policy: DotfilePolicy) -> Self {
+        self.dotfiles = policy;
+        self
+    }
+
+    /// # PublicFolder::with_index_file
+    ///
+    /// Sets the file served for requests to a directory, `index.html` by default, or `None` to not serve index files
+    pub fn with_index_file(mut self, index_file: Option<&str>) -> Self {
+        self.index_file = index_file.map(String::from);
+        self
+    }
+
+    /// # PublicFolder::with_listing
+    ///
+    /// Enables listing the contents of directories without an index file, including file sizes and modification times
+    ///
+    /// The listing is sent as html, or as json to clients that accept `application/json` but not `text/html`.
+    /// Dotfiles are only listed with `DotfilePolicy::Allow`, and symbolic links only if the symlink policy lets them be requested.
+    pub fn with_listing(mut self, enabled: bool) -> Self {
+        self.listing = enabled;
+        self
+    }
+
+    /// # PublicFolder::with_cache_control
+    ///
+    /// Sets the `Cache-Control` header sent with the files of the folder, by default none is sent
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// // Files with a content hash in their name never change
+    /// PublicFolder::new("./dist").with_cache_control("public, max-age=31536000, immutable");
+    /// ```
+    pub fn with_cache_control(mut self, cache_control: &str) -> Self {
+        self.cache_control = Some(cache_control.to_string());
+        self
+    }
+
+    /// # PublicFolder::with_spa_fallback
+    ///
+    /// Serves a file, usually `index.html`, for paths without a file so that a single-page app can handle its own routes,
+    /// or `None` to disable the fallback, which is the default
+    ///
+    /// The fallback is only used for `GET` and `HEAD` requests that accept `text/html` and only after none of the
+    /// mounted folders has a file for the path. Paths whose last segment has an extension, like `/app.js`, still get a 404,
+    /// so that missing assets are not answered with html.
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// let mut handler = RequestHandler::new();
+    /// handler.set_public_folder(
+    ///     PublicFolder::new("./dist")
+    ///         .with_spa_fallback(Some("index.html"))
+    ///         .with_spa_exclude("/api"),
+    /// );
+    /// ```
+    pub fn with_spa_fallback(mut self, file: Option<&str>) -> Self {
+        self.spa_fallback = file.map(String::from);
+        self
+    }
+
+    /// # PublicFolder::with_spa_exclude
+    ///
+    /// Excludes the request paths under a prefix from the single-page app fallback, e.g. `/api`
+    ///
+    /// The prefix is compared with the whole request path and only matches whole segments.
+    pub fn with_spa_exclude(mut self, prefix: &str) -> Self {
+        self.spa_excludes.push(prefix.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// # PublicFolder::get_root
+    ///
+    /// Returns the folder the files are served from
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Answers a request for `path` inside of the folder, which is relative to the prefix the folder is mounted at,
+    /// or returns `None` if there is no such file
+    pub(crate) fn serve(&self, request: &HTTPRequest, path: &str, mime: &MimeRegistry) -> Option<HTTPResponse> {
+        match resolve(&self.root, path, self.symlinks, self.dotfiles) {
+            Resolved::File(file) => Some(self.serve_file(request, path, &file, mime)),
+            Resolved::Directory(dir) => self.serve_directory(request, path, &dir, mime),
+            Resolved::Forbidden => Some(HTTPResponse::error(HTTPStatusCode::Forbidden)),
+            Resolved::NotFound => None,
+        }
+    }
+
+    /// Answers a request that no folder has a file for with the single-page app fallback, if it applies to the request
+    pub(crate) fn serve_fallback(&self, request: &HTTPRequest, mime: &MimeRegistry) -> Option<HTTPResponse> {
+        let fallback = self.spa_fallback.as_ref()?;
+
+        if !matches!(request.get_method(), HTTPMethod::Get | HTTPMethod::Head) || !accepts_html(request) {
+            return None;
+        }
+
+        // Missing assets should not be answered with the app
+        let path = request.get_path();
+        let last_segment = path.rsplit('/').next().unwrap_or_default();
+        if last_segment.contains('.') {
+            return None;
+        }
+
+        let excluded = self.spa_excludes.iter().any(|prefix| match path.strip_prefix(prefix.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        });
+        if excluded {
+            return None;
+        }
+
+        let fallback_path = format!("/{}", fallback.trim_start_matches('/'));
+        match resolve(&self.root, &fallback_path, self.symlinks, self.dotfiles) {
+            Resolved::File(file) => Some(self.serve_file(request, &fallback_path, &file, mime)),
+            _ => None,
+        }
+    }
+
+    fn serve_directory(&self, request: &HTTPRequest, path: &str, dir: &Path, mime: &MimeRegistry) -> Option<HTTPResponse> {
+        // Relative links in the index file only work if the path ends with a slash
+        if !path.ends_with('/') {
+            return Some(
+                HTTPResponse::builder()
+                    .status(HTTPStatusCode::MovedPermanently)
+                    .header("Location", &directory_location(request))
+                    .build(),
+            );
+        }
+
+        // The index file goes through the same checks as a direct request for it
+        if let Some(index_file) = &self.index_file {
+            let index_path = format!("{}{}", path, index_file);
+            match resolve(&self.root, &index_path, self.symlinks, self.dotfiles) {
+                Resolved::File(index) => return Some(self.serve_file(request, &index_path, &index, mime)),
+                Resolved::Forbidden => return Some(HTTPResponse::error(HTTPStatusCode::Forbidden)),
+                Resolved::Directory(_) | Resolved::NotFound => {}
+            }
+        }
+
+        if !self.listing {
+            return None;
+        }
+
+        match listing::render(request, self, dir, path) {
+            Ok(response) => Some(response),
+            Err(_) => Some(HTTPResponse::builder().status(HTTPStatusCode::InternalServerError).build()),
+        }
+    }
+
+    fn serve_file(&self, request: &HTTPRequest, path: &str, file: &Path, mime: &MimeRegistry) -> HTTPResponse {
+        // Precompressed variants keep the type of the original file
+        let content_type = mime.content_type(file);
+
+        let sidecars = precompressed::find(&self.root, path, self.symlinks, self.dotfiles);
+        let sidecar = precompressed::choose(request, &sidecars);
+        // Some formats are stored compressed, their content coding is part of the file itself
+        let encoding = sidecar.map(|sidecar| sidecar.encoding).or_else(|| stored_encoding(file));
+        let file = sidecar.map_or(file, |sidecar| &sidecar.path);
+
+        match Self::open_file(request, file, &content_type) {
+            Ok(mut response) => {
+                response.set_header("Accept-Ranges", "bytes");
+                if let Some(encoding) = encoding.filter(|_| response.get_stream().is_some()) {
+                    response.set_header("Content-Encoding", encoding);
+                }
+                // Caches have to know that the response depends on the header, also when the plain file was sent
+                if !sidecars.is_empty() {
+                    response.set_header("Vary", "Accept-Encoding");
+                }
+                if let Some(cache_control) = &self.cache_control {
+                    response.set_header("Cache-Control", cache_control);
+                }
+                response
+            }
+            Err(_) => HTTPResponse::builder().status(HTTPStatusCode::InternalServerError).build(),
+        }
+    }
+
+    fn open_file(request: &HTTPRequest, path: &Path, content_type: &str) -> io::Result<HTTPResponse> {
+        let file = File::open(path)?;
+        let metadata = file.metadata()?;
+        let length = metadata.len();
+        let modified = metadata.modified().ok();
+        let etag = conditional::etag(&metadata);
+
+        let mut response = match conditional::evaluate(request, &etag, modified) {
+            Some(response) => response,
+            None => Self::read_file(request, file, path, content_type, length, &etag, modified)?,
+        };
+
+        if *response.get_status() != HTTPStatusCode::PreconditionFailed {
+            response.set_header("ETag", &etag);
+            if let Some(modified) = modified {
+                response.set_header("Last-Modified", &format_http_date(modified));
+            }
+        }
+        Ok(response)
+    }
+
+    fn read_file(
+        request: &HTTPRequest,
+        file: File,
+        path: &Path,
+        content_type: &str,
+        length: u64,
+        etag: &str,
+        modified: Option<SystemTime>,
+    ) -> io::Result<HTTPResponse> {
+        // Ranges only apply to GET, and only if the client's copy of the file is still current
+        let use_ranges = *request.get_method() == HTTPMethod::Get
+            && range::if_range_matches(request.get_header("If-Range"), etag, modified);
+        let range = if use_ranges {
+            range::parse(request.get_header("Range"), length)
+        } else {
+            RangeRequest::Full
+        };
+
+        match range {
+            RangeRequest::Full => Ok(HTTPResponse::builder()
+                .header("Content-Type", content_type)
+                .stream(BodyStream::from_reader(file, Some(length)))
+                .build()),
+            RangeRequest::Partial(ranges) => range::partial_response(path, content_type, length, &ranges),
+            RangeRequest::Unsatisfiable => Ok(range::unsatisfiable_response(length)),
+        }
+    }
+}
+
+/// Builds the path of the requested directory with a trailing slash, keeping the query
+///
+/// The location is built from the decoded path and query instead of the raw target, so that it is always a valid header value.
+/// Empty segments are dropped, `//host` would otherwise redirect to another host.
+fn directory_location(request: &HTTPRequest) -> String {
+    let mut location = String::from("/");
+    for segment in request.get_path().split('/').filter(|segment| !segment.is_empty()) {
+        location.push_str(&percent_encode(segment));
+        location.push('/');
+    }
+
+    let query = request
+        .get_query()
+        .iter()
+        .map(|(key, value)| format!("{}={}", percent_encode(key), percent_encode(value)))
+        .collect::<Vec<_>>();
+    if !query.is_empty() {
+        location.push('?');
+        location.push_str(&query.join("&"));
+    }
+    location
+}
+
+/// Returns whether the `Accept` header of the request allows `text/html`, browsers send it when navigating to a page
+/// Returns the content coding of files that are always stored compressed, like `.svgz`
+fn stored_encoding(file: &Path) -> Option<&'static str> {
+    file.extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| extension.eq_ignore_ascii_case("svgz"))
+        .map(|_| "gzip")
+}
+
+fn accepts_html(request: &HTTPRequest) -> bool {
+    accept_quality(request, "text/html") > 0.0
+}
+
+/// Returns the quality the `Accept` header gives a media type by naming it, wildcards are not taken into account
+pub(super) fn accept_quality(request: &HTTPRequest, media_type: &str) -> f32 {
+    request
+        .get_headers()
+        .get_all("Accept")
+        .flat_map(|accept| accept.split(','))
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            if !parts.next().unwrap_or_default().eq_ignore_ascii_case(media_type) {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            Some(quality)
+        })
+        .fold(0.0, f32::max)
+}
+
+impl From<&str> for PublicFolder {
+    fn from(root: &str) -> Self {
+        Self::new(root)
+    }
+}
+
+impl From<PathBuf> for PublicFolder {
+    fn from(root: PathBuf) -> Self {
+        Self::new(root)
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    fs::Metadata,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::http::{date::parse_http_date, HTTPMethod, HTTPRequest, HTTPResponse, HTTPStatusCode};
+
+use super::range::truncate_to_seconds;
+
+/// Returns a strong entity tag for a file, built from its size and modification time
+pub(super) fn etag(metadata: &Metadata) -> String {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+
+    format!("\"{:x}-{:x}\"", metadata.len(), modified)
+}
+
+/// Evaluates the conditional headers of a request in the order given by RFC 9110 section 13.2.2
+///
+/// Returns the `304 Not Modified` or `412 Precondition Failed` response if a condition decides the request,
+/// or `None` if the file should be sent
+pub(super) fn evaluate(request: &HTTPRequest, etag: &str, modified: Option<SystemTime>) -> Option<HTTPResponse> {
+    let modified = modified.map(truncate_to_seconds);
+    let is_get = matches!(request.get_method(), HTTPMethod::Get | HTTPMethod::Head);
+
+    if let Some(if_match) = header_list(request, "If-Match") {
+        if !matches_any(&if_match, etag, true) {
+            return Some(HTTPResponse::error(HTTPStatusCode::PreconditionFailed));
+        }
+    } else if let Some(since) = request.get_header("If-Unmodified-Since").and_then(parse_http_date) {
+        if modified.is_some_and(|modified| modified > since) {
+            return Some(HTTPResponse::error(HTTPStatusCode::PreconditionFailed));
+        }
+    }
+
+    if let Some(if_none_match) = header_list(request, "If-None-Match") {
+        if matches_any(&if_none_match, etag, false) {
+            return Some(not_modified_or_failed(is_get));
+        }
+    } else if let Some(since) = request.get_header("If-Modified-Since").and_then(parse_http_date) {
+        if is_get && modified.is_some_and(|modified| modified <= since) {
+            return Some(not_modified_or_failed(is_get));
+        }
+    }
+
+    None
+}
+
+fn not_modified_or_failed(is_get: bool) -> HTTPResponse {
+    if is_get {
+        HTTPResponse::builder().status(HTTPStatusCode::NotModified).build()
+    } else {
+        HTTPResponse::error(HTTPStatusCode::PreconditionFailed)
+    }
+}
+
+/// Joins the values of a header that may be sent several times into one list
+fn header_list(request: &HTTPRequest, name: &str) -> Option<String> {
+    let values = request.get_headers().get_all(name).collect::<Vec<_>>();
+    (!values.is_empty()).then(|| values.join(","))
+}
+
+fn matches_any(list: &str, etag: &str, strong: bool) -> bool {
+    if list.trim() == "*" {
+        return true;
+    }
+
+    parse_tags(list).into_iter().any(|tag| {
+        if strong {
+            strong_eq(tag, etag)
+        } else {
+            tag.trim_start_matches("W/") == etag.trim_start_matches("W/")
+        }
+    })
+}
+
+/// Two entity tags are only strongly equal if neither of them is weak
+pub(super) fn strong_eq(a: &str, b: &str) -> bool {
+    !a.starts_with("W/") && !b.starts_with("W/") && a == b
+}
+
+/// Splits a list of entity tags, which may contain commas inside of their quotes
+fn parse_tags(list: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+
+    for (index, char) in list.char_indices() {
+        match char {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                tags.push(list[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    tags.push(list[start..].trim());
+
+    tags.retain(|tag| !tag.is_empty());
+    tags
+}
@@ -0,0 +1,135 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::http::{date::format_http_date, url::percent_encode, HTTPRequest, HTTPResponse};
+
+use super::{
+    accept_quality,
+    resolve::{resolve, Resolved},
+    PublicFolder,
+};
+
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Lists the contents of a directory as html, or as json if the client prefers it
+///
+/// `path` is the path of the directory inside of the folder, ending with `/`.
+/// Only entries that a request would be answered with are listed, following the symlink and dotfile policies of the folder
+pub(super) fn render(request: &HTTPRequest, folder: &PublicFolder, dir: &Path, path: &str) -> io::Result<HTTPResponse> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(_) => continue,
+        };
+
+        let resolved = match resolve(&folder.root, &format!("{}{}", path, name), folder.symlinks, folder.dotfiles) {
+            Resolved::File(resolved) | Resolved::Directory(resolved) => resolved,
+            Resolved::NotFound | Resolved::Forbidden => continue,
+        };
+        // Follow symbolic links to list what a request for the entry would return
+        let metadata = match fs::metadata(resolved) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        entries.push(Entry {
+            name,
+            is_dir: metadata.is_dir(),
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: metadata.modified().ok(),
+        });
+    }
+
+    // Directories first, then alphabetically
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let wants_json = accept_quality(request, "application/json") > accept_quality(request, "text/html");
+
+    if wants_json {
+        Ok(HTTPResponse::json(json(&entries)))
+    } else {
+        Ok(HTTPResponse::html(html(&entries, request.get_path(), path == "/")))
+    }
+}
+
+/// `title` is the full request path, the folder might be mounted under a prefix
+fn html(entries: &[Entry], title: &str, is_root: bool) -> String {
+    let title = escape_html(title);
+    let mut rows = String::new();
+
+    if !is_root {
+        rows.push_str("        <tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir { String::from("-") } else { entry.size.to_string() };
+        let modified = entry.modified.map(format_http_date).unwrap_or_default();
+
+        rows.push_str(&format!(
+            "        <tr><td><a href=\"{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            percent_encode(&entry.name),
+            suffix,
+            escape_html(&entry.name),
+            suffix,
+            size,
+            modified
+        ));
+    }
+
+    include_str!("../../assets/listing.html")
+        .replace("{title}", &title)
+        .replace("{rows}", rows.trim_end())
+}
+
+fn json(entries: &[Entry]) -> String {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let modified = entry
+                .modified
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| modified.as_secs().to_string())
+                .unwrap_or_else(|| String::from("null"));
+
+            format!(
+                "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{},\"modified\":{}}}",
+                escape_json(&entry.name),
+                if entry.is_dir { "directory" } else { "file" },
+                entry.size,
+                modified
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", entries.join(","))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            char if char.is_control() => escaped.push_str(&format!("\\u{:04x}", char as u32)),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+
+use crate::http::{encoding, HTTPRequest};
+
+use super::resolve::{resolve, DotfilePolicy, Resolved, SymlinkPolicy};
+
+/// Content codings of precompressed files and the extensions of their files, in order of preference
+const SIDECARS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// A precompressed variant of a file, stored next to it with an additional extension
+pub(super) struct Sidecar {
+    pub(super) encoding: &'static str,
+    pub(super) path: PathBuf,
+}
+
+/// Finds the precompressed variants of the file at the request path `path`
+///
+/// The variants are resolved like a request for them, so that they are subject to the same policies as the file itself
+pub(super) fn find(root: &Path, path: &str, symlinks: SymlinkPolicy, dotfiles: DotfilePolicy) -> Vec<Sidecar> {
+    SIDECARS
+        .iter()
+        .filter_map(|(encoding, extension)| match resolve(root, &format!("{}.{}", path, extension), symlinks, dotfiles) {
+            Resolved::File(path) => Some(Sidecar { encoding, path }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Picks the variant the client accepts with the highest quality, preferring brotli if they are equal
+pub(super) fn choose<'a>(request: &HTTPRequest, sidecars: &'a [Sidecar]) -> Option<&'a Sidecar> {
+    let accept_encoding = request.get_header("Accept-Encoding");
+    let mut best: Option<(&Sidecar, f32)> = None;
+
+    for sidecar in sidecars {
+        let quality = encoding::quality(accept_encoding, sidecar.encoding);
+        if quality > 0.0 && best.is_none_or(|(_, best)| quality > best) {
+            best = Some((sidecar, quality));
+        }
+    }
+    best.map(|(sidecar, _)| sidecar)
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::http::{date::parse_http_date, BodyStream, HTTPResponse, HTTPStatusCode};
+
+use super::conditional;
+
+/// More ranges than this in a single request are ignored and the whole file is sent
+const MAX_RANGES: usize = 64;
+
+/// The part of a file a `Range` header asks for
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum RangeRequest {
+    /// The header is missing or can not be used, the whole file is sent
+    Full,
+    /// Inclusive byte ranges, sorted and with overlapping ranges merged
+    Partial(Vec<(u64, u64)>),
+    /// None of the ranges overlap the file
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header for a file of the given length, see RFC 9110 section 14.2
+pub(super) fn parse(header: Option<&str>, length: u64) -> RangeRequest {
+    let specs = match header.and_then(|header| header.trim().strip_prefix("bytes=")) {
+        Some(specs) => specs,
+        None => return RangeRequest::Full,
+    };
+
+    let mut ranges = Vec::new();
+    for spec in specs.split(',').map(str::trim).filter(|spec| !spec.is_empty()) {
+        let (start, end) = match spec.split_once('-') {
+            Some(bounds) => bounds,
+            None => return RangeRequest::Full,
+        };
+
+        let range = match (start.parse::<u64>(), end.parse::<u64>()) {
+            // bytes=-500 are the last 500 bytes
+            (Err(_), Ok(suffix)) if start.is_empty() => {
+                if suffix == 0 || length == 0 {
+                    None
+                } else {
+                    Some((length.saturating_sub(suffix), length - 1))
+                }
+            }
+            // bytes=500- are all bytes from the 500th on
+            (Ok(start), Err(_)) if end.is_empty() => (start < length).then(|| (start, length - 1)),
+            (Ok(start), Ok(end)) if start <= end => (start < length).then(|| (start, end.min(length - 1))),
+            _ => return RangeRequest::Full,
+        };
+        ranges.extend(range);
+
+        if ranges.len() > MAX_RANGES {
+            return RangeRequest::Full;
+        }
+    }
+
+    if ranges.is_empty() {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    ranges.sort();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    RangeRequest::Partial(merged)
+}
+
+/// Returns whether the ranges should be applied given the `If-Range` header, i.e. the client's copy is still current
+///
+/// Entity tags have to match `etag` strongly, dates have to be the exact modification time of the file.
+pub(super) fn if_range_matches(if_range: Option<&str>, etag: &str, modified: Option<SystemTime>) -> bool {
+    let if_range = match if_range {
+        Some(if_range) => if_range.trim(),
+        None => return true,
+    };
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return conditional::strong_eq(if_range, etag);
+    }
+
+    match (parse_http_date(if_range), modified) {
+        (Some(date), Some(modified)) => truncate_to_seconds(modified) == date,
+        _ => false,
+    }
+}
+
+/// Builds a `206 Partial Content` response for the ranges of a file,
+/// using `multipart/byteranges` if there is more than one range
+pub(super) fn partial_response(path: &Path, content_type: &str, length: u64, ranges: &[(u64, u64)]) -> io::Result<HTTPResponse> {
+    let builder = HTTPResponse::builder().status(HTTPStatusCode::PartialContent);
+
+    if let [(start, end)] = ranges {
+        let stream = BodyStream::from_reader(open_range(path, *start, *end)?, Some(end - start + 1));
+        return Ok(builder
+            .header("Content-Type", content_type)
+            .header("Content-Range", &format!("bytes {}-{}/{}", start, end, length))
+            .stream(stream)
+            .build());
+    }
+
+    let boundary = boundary();
+    let mut body: Box<dyn Read + Send> = Box::new(io::empty());
+    let mut body_length = 0;
+
+    for (start, end) in ranges {
+        let head = format!(
+            "--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+            boundary, content_type, start, end, length
+        );
+        body_length += head.len() as u64 + (end - start + 1) + 2;
+        body = Box::new(body.chain(Cursor::new(head)).chain(open_range(path, *start, *end)?).chain(&b"\r\n"[..]));
+    }
+
+    let tail = format!("--{}--\r\n", boundary);
+    body_length += tail.len() as u64;
+    body = Box::new(body.chain(Cursor::new(tail)));
+
+    Ok(builder
+        .header("Content-Type", &format!("multipart/byteranges; boundary={}", boundary))
+        .stream(BodyStream::from_reader(body, Some(body_length)))
+        .build())
+}
+
+/// Builds a `416 Range Not Satisfiable` response for a file of the given length
+pub(super) fn unsatisfiable_response(length: u64) -> HTTPResponse {
+    HTTPResponse::builder()
+        .status(HTTPStatusCode::RangeNotSatisfiable)
+        .header("Content-Range", &format!("bytes */{}", length))
+        .build()
+}
+
+/// HTTP dates only have a precision of seconds
+pub(super) fn truncate_to_seconds(time: SystemTime) -> SystemTime {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+fn open_range(path: &Path, start: u64, end: u64) -> io::Result<io::Take<File>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    Ok(file.take(end - start + 1))
+}
+
+/// A boundary that is unique within the process, file contents could only collide with it by chance
+fn boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or_default();
+    format!("carola-{:08x}{:08x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
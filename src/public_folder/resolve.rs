@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// # SymlinkPolicy
+///
+/// Decides whether symbolic links inside a public folder are followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Follow every symbolic link, even if it points outside of the folder
+    Follow,
+    /// Follow symbolic links as long as they point to a file inside of the folder
+    #[default]
+    WithinRoot,
+    /// Refuse to serve any path that goes through a symbolic link
+    Deny,
+}
+
+/// # DotfilePolicy
+///
+/// Decides how paths with a segment starting with `.`, e.g. `/.env` or `/.git/config`, are answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DotfilePolicy {
+    /// Serve dotfiles like any other file
+    Allow,
+    /// Answer with `403 Forbidden`
+    Deny,
+    /// Act as if the file did not exist
+    #[default]
+    Ignore,
+}
+
+/// What a request path points to inside of a public folder
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Resolved {
+    File(PathBuf),
+    Directory(PathBuf),
+    NotFound,
+    Forbidden,
+}
+
+/// Maps an already percent-decoded request path onto a file inside of the root folder.
+///
+/// `.` and `..` segments are normalized before the file system is touched, so that the path can never climb above the root,
+/// and the final file is checked against the canonical root to catch symbolic links pointing outside of it.
+pub(crate) fn resolve(root: &Path, path: &str, symlinks: SymlinkPolicy, dotfiles: DotfilePolicy) -> Resolved {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Resolved::Forbidden;
+                }
+            }
+            // Backslashes are separators and `C:` a drive prefix on some platforms
+            _ if !is_normal_segment(segment) => return Resolved::Forbidden,
+            _ => segments.push(segment),
+        }
+    }
+
+    if segments.iter().any(|segment| segment.starts_with('.')) {
+        match dotfiles {
+            DotfilePolicy::Allow => {}
+            DotfilePolicy::Deny => return Resolved::Forbidden,
+            DotfilePolicy::Ignore => return Resolved::NotFound,
+        }
+    }
+
+    let root = match fs::canonicalize(root) {
+        Ok(root) => root,
+        Err(_) => return Resolved::NotFound,
+    };
+
+    let mut candidate = root.clone();
+    for segment in segments {
+        candidate.push(segment);
+        let is_symlink = fs::symlink_metadata(&candidate).is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if is_symlink && symlinks == SymlinkPolicy::Deny {
+            return Resolved::Forbidden;
+        }
+    }
+
+    let target = match fs::canonicalize(&candidate) {
+        Ok(target) => target,
+        Err(_) => return Resolved::NotFound,
+    };
+    if symlinks == SymlinkPolicy::WithinRoot && !target.starts_with(&root) {
+        return Resolved::Forbidden;
+    }
+
+    match fs::metadata(&target) {
+        Ok(metadata) if metadata.is_file() => Resolved::File(candidate),
+        Ok(metadata) if metadata.is_dir() => Resolved::Directory(candidate),
+        _ => Resolved::NotFound,
+    }
+}
+
+fn is_normal_segment(segment: &str) -> bool {
+    if segment.contains(['\\', '\0']) {
+        return false;
+    }
+
+    let mut components = Path::new(segment).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
@@ -0,0 +1,342 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use crate::http::{url, HTTPMethod, HTTPRequest, HTTPResponse, HTTPStatusCode, IntoResponse};
+
+use self::listener::RequestListener;
+
+mod listener;
+
+pub mod tree;
+pub use tree::{RouteError, RouteMatch, RouteTree};
+
+/// # Router
+///
+/// A group of listeners that can be built on its own and mounted under a prefix,
+/// either on a `RequestHandler` or on another router
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::handler::RequestHandler;
+/// use carola::router::Router;
+/// use carola::http::{HTTPRequest, HTTPResponse, HTTPStatusCode};
+///
+/// let mut api = Router::new();
+/// api.set_listener("GET", "/users/:id", |request: HTTPRequest| {
+///    format!("User {}", request.param("id").unwrap())
+/// });
+///
+/// let mut handler = RequestHandler::new();
+/// handler.mount("/api/v1", api); // Serves GET /api/v1/users/:id
+/// ```
+pub struct Router {
+    listeners: HashMap<HTTPMethod, RouteTree<RequestListener<'static>>>,
+    mounts: Vec<(String, Router)>,
+    not_found_callback: Option<Box<dyn Fn(HTTPRequest) -> HTTPResponse + Send + Sync + 'static>>,
+    method_not_allowed_callback: Box<dyn Fn(HTTPRequest) -> HTTPResponse + Send + Sync + 'static>
+}
+
+impl Router {
+    /// # Router::new
+    ///
+    /// Creates a new router without any listeners
+    pub fn new() -> Self {
+        Router {
+            listeners: HashMap::new(),
+            mounts: Vec::new(),
+            not_found_callback: None,
+            method_not_allowed_callback: Box::new(Self::default405)
+        }
+    }
+
+    /// # Router::set_listener
+    ///
+    /// Sets a listener for a specific path and http method
+    ///
+    /// The path may contain parameter segments (`:name`) matching a single path segment,
+    /// and a trailing wildcard segment (`*name`) matching the rest of the path.
+    /// The captured values are available through `HTTPRequest::param`.
+    /// Request paths are split into segments before they are decoded, so `/users/a%2Fb` matches `/users/:id`.
+    ///
+    /// When several paths match a request, static segments win over parameters and parameters over wildcards.
+    ///
+    /// ## Arguments
+    ///
+    /// * `method` - The http method to be used on this path
+    /// * `path` - The path to listen on, relative to the prefix the router is mounted on
+    /// * `callback` - The callback to be called when a request is made to this path, returning anything that implements `IntoResponse`
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the path is malformed or conflicts with a listener that is already registered for the same method,
+    /// e.g. the same path twice or `/users/:id` next to `/users/:name`
+    pub fn set_listener<C, R>(&mut self, method: &str, path: &str, callback: C)
+    where
+        C: 'static + Send + Sync + Fn(HTTPRequest) -> R,
+        R: IntoResponse,
+    {
+        let method = HTTPMethod::from(method);
+        let listener = RequestListener::new(path.to_string(), method.clone(), move |request| callback(request).into_response());
+
+        if let Err(err) = self.listeners.entry(method).or_default().insert(path, listener) {
+            panic!("{}", err);
+        }
+    }
+
+    /// # Router::set_not_found_callback
+    ///
+    /// Sets the callback to be called when a request under the prefix of this router matches none of its listeners
+    ///
+    /// Without a callback, unmatched requests are passed back to the router or handler this router is mounted on
+    pub fn set_not_found_callback<C: 'static + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>(&mut self, callback: C) {
+        self.not_found_callback = Some(Box::new(callback));
+    }
+
+    /// # Router::set_method_not_allowed_callback
+    ///
+    /// Sets the callback to be called when a request is made to a path that has listeners, but none for the requested method
+    ///
+    /// The `Allow` header listing the methods registered for the path is added to the response automatically,
+    /// unless the callback sets it itself
+    pub fn set_method_not_allowed_callback<C: 'static + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>(&mut self, callback: C) {
+        self.method_not_allowed_callback = Box::new(callback);
+    }
+
+    /// # Router::mount
+    ///
+    /// Mounts another router under a static prefix, so that its listeners are served at `prefix + path`
+    ///
+    /// Listeners of this router take precedence over mounted routers, and longer prefixes over shorter ones.
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// let mut admin = Router::new();
+    /// admin.set_listener("GET", "/stats", stats);
+    ///
+    /// let mut router = Router::new();
+    /// router.mount("/admin", admin); // Serves GET /admin/stats
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if the prefix does not start with `/` or contains parameters
+    pub fn mount(&mut self, prefix: &str, router: Router) {
+        if !prefix.starts_with('/') || prefix.contains([':', '*']) {
+            panic!("invalid mount prefix '{}': prefixes must be static and start with '/'", prefix);
+        }
+
+        let prefix = prefix.trim_end_matches('/').to_string();
+        let index = self
+            .mounts
+            .iter()
+            .position(|(existing, _)| existing.len() < prefix.len())
+            .unwrap_or(self.mounts.len());
+        self.mounts.insert(index, (prefix, router));
+    }
+
+    /// # Router::handle
+    ///
+    /// Matches a request against this router on its own and returns the response,
+    /// falling back to a 404 page if the request is not handled
+    ///
+    /// ## Example
+    ///
+    /// ```rs
+    /// let response = router.handle(HTTPRequest::parse(vec!["GET /stats HTTP/1.1"])?);
+    /// assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    /// ```
+    pub fn handle(&self, request: HTTPRequest) -> HTTPResponse {
+        let path = request.get_route_path().to_owned();
+        let head = *request.get_method() == HTTPMethod::Head;
+        let mut response = self.route(&path, request).unwrap_or_else(Self::default404);
+        if head {
+            response.strip_body();
+        }
+        response
+    }
+
+    /// Routes the request using the given path, which is relative to the prefix of this router.
+    ///
+    /// `HEAD` and `OPTIONS` requests are answered automatically unless a listener is registered for them:
+    /// `HEAD` runs the `GET` listener and drops the body, `OPTIONS` lists the methods allowed on the path.
+    ///
+    /// Gives the request back if nothing in this router handles it.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn route(&self, path: &str, request: HTTPRequest) -> Result<HTTPResponse, HTTPRequest> {
+        let method = request.get_method().clone();
+
+        match method {
+            HTTPMethod::Head if !self.has_listener(&method, path) => self.dispatch(&HTTPMethod::Get, path, request),
+            HTTPMethod::Options if !self.has_listener(&method, path) => {
+                let allow = if path == "*" {
+                    Self::format_allow(self.all_methods())
+                } else {
+                    self.allow_header(path)
+                };
+
+                match allow {
+                    Some(allow) => Ok(HTTPResponse::builder()
+                        .status(HTTPStatusCode::NoContent)
+                        .header("Allow", &allow)
+                        .build()),
+                    None => self.dispatch(&method, path, request),
+                }
+            }
+            _ => self.dispatch(&method, path, request),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn dispatch(&self, method: &HTTPMethod, path: &str, mut request: HTTPRequest) -> Result<HTTPResponse, HTTPRequest> {
+        let matched = self
+            .listeners
+            .get(method)
+            .and_then(|tree| tree.at(path));
+
+        // If there is a listener for this path, call it
+        if let Some(matched) = matched {
+            // The values were matched with `/` and `%` still encoded
+            let params = matched
+                .params
+                .into_iter()
+                .map(|(name, value)| (name, url::percent_decode(&value).unwrap_or(value)))
+                .collect();
+            request.set_params(params);
+            return Ok((matched.value.callback)(request));
+        }
+
+        // Hand the request to the routers mounted on a prefix of the path
+        for (prefix, router) in &self.mounts {
+            if let Some(rest) = strip_mount_prefix(prefix, path) {
+                match router.route(rest, request) {
+                    Ok(response) => return Ok(response),
+                    Err(unhandled) => request = unhandled,
+                }
+            }
+        }
+
+        // If the path exists for other methods, the method is not allowed
+        if let Some(allow) = self.allow_header(path) {
+            return Ok(self.method_not_allowed(request, &allow));
+        }
+
+        match &self.not_found_callback {
+            Some(callback) => Ok(callback(request)),
+            None => Err(request),
+        }
+    }
+
+    /// Answers a request with the method not allowed callback, adding the `Allow` header unless the callback set it
+    pub(crate) fn method_not_allowed(&self, request: HTTPRequest, allow: &str) -> HTTPResponse {
+        let mut response = (self.method_not_allowed_callback)(request);
+        if !response.get_headers().contains_key("Allow") {
+            response.set_header("Allow", allow);
+        }
+        response
+    }
+
+    /// # Router::allowed_methods
+    ///
+    /// Returns the methods that have a listener matching the given path, in a stable order
+    ///
+    /// This includes `HEAD` and `OPTIONS` when they are handled automatically
+    pub fn allowed_methods(&self, path: &str) -> Vec<HTTPMethod> {
+        let mut methods = self
+            .listeners
+            .iter()
+            .filter(|(_, tree)| tree.at(path).is_some())
+            .map(|(method, _)| method.clone())
+            .collect::<Vec<_>>();
+
+        for (prefix, router) in &self.mounts {
+            if let Some(rest) = strip_mount_prefix(prefix, path) {
+                methods.extend(router.allowed_methods(rest));
+            }
+        }
+
+        Self::with_implicit_methods(methods)
+    }
+
+    /// Every method registered on any path, used for `OPTIONS *`
+    fn all_methods(&self) -> Vec<HTTPMethod> {
+        let mut methods = self
+            .listeners
+            .iter()
+            .filter(|(_, tree)| !tree.is_empty())
+            .map(|(method, _)| method.clone())
+            .collect::<Vec<_>>();
+
+        for (_, router) in &self.mounts {
+            methods.extend(router.all_methods());
+        }
+
+        Self::with_implicit_methods(methods)
+    }
+
+    fn has_listener(&self, method: &HTTPMethod, path: &str) -> bool {
+        self.listeners
+            .get(method)
+            .is_some_and(|tree| tree.at(path).is_some())
+    }
+
+    fn with_implicit_methods(mut methods: Vec<HTTPMethod>) -> Vec<HTTPMethod> {
+        if methods.is_empty() {
+            return methods;
+        }
+        if methods.contains(&HTTPMethod::Get) {
+            methods.push(HTTPMethod::Head);
+        }
+        methods.push(HTTPMethod::Options);
+
+        methods.sort();
+        methods.dedup();
+        methods
+    }
+
+    fn allow_header(&self, path: &str) -> Option<String> {
+        Self::format_allow(self.allowed_methods(path))
+    }
+
+    fn format_allow(methods: Vec<HTTPMethod>) -> Option<String> {
+        if methods.is_empty() {
+            return None;
+        }
+
+        Some(methods.iter().map(|method| method.to_string()).collect::<Vec<_>>().join(", "))
+    }
+
+    pub(crate) fn default404(_: HTTPRequest) -> HTTPResponse {
+        HTTPResponse::not_found()
+    }
+
+    fn default405(_: HTTPRequest) -> HTTPResponse {
+        HTTPResponse::builder()
+            .status(HTTPStatusCode::MethodNotAllowed)
+            .html(include_str!("../assets/405.html"))
+    }
+}
+
+/// Returns the part of the path after the mount prefix, or `None` if the path is not under the prefix
+fn strip_mount_prefix<'p>(prefix: &str, path: &'p str) -> Option<&'p str> {
+    match path.strip_prefix(prefix)? {
+        "" => Some("/"),
+        rest if rest.starts_with('/') => Some(rest),
+        _ => None,
+    }
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Router {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Router")
+            .field("listeners", &self.listeners)
+            .field("mounts", &self.mounts)
+            .finish()
+    }
+}
@@ -5,11 +5,11 @@ use crate::http::{HTTPMethod, HTTPRequest, HTTPResponse};
 pub struct RequestListener<'a> {
    pub path: String,
    pub method: HTTPMethod,
-   pub callback: Box<dyn 'a + Send + FnMut(HTTPRequest) -> HTTPResponse> 
+   pub callback: Box<dyn 'a + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>
 }
 
 impl<'a> RequestListener<'a> {
-    pub fn new<C: 'a + Send + FnMut(HTTPRequest) -> HTTPResponse>(path: String, method: HTTPMethod, callback: C) -> Self {
+    pub fn new<C: 'a + Send + Sync + Fn(HTTPRequest) -> HTTPResponse>(path: String, method: HTTPMethod, callback: C) -> Self {
         Self {
             path,
             method,
@@ -25,4 +25,4 @@ impl Debug for RequestListener<'_> {
             .field("method", &self.method)
             .finish()
     }
-}
\ No newline at end of file
+}
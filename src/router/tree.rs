@@ -0,0 +1,333 @@
+use std::{collections::HashMap, error::Error, fmt::{self, Debug, Display}};
+
+/// # RouteTree
+///
+/// A compressed prefix tree (radix tree) mapping path patterns to values.
+///
+/// Patterns are made out of three kinds of segments:
+///
+/// * Static text, e.g. `/users/all`, shared between routes with a common prefix
+/// * Parameters, e.g. `:id`, matching a single non-empty path segment
+/// * Catch-alls, e.g. `*rest`, matching the rest of the path. These must be the last segment of a pattern
+///
+/// When several routes match a path, static segments are preferred over parameters and parameters over catch-alls.
+///
+/// ## Example
+///
+/// ```rs
+/// use carola::router::RouteTree;
+///
+/// let mut tree = RouteTree::new();
+/// tree.insert("/users/:id", "user").unwrap();
+/// tree.insert("/users/me", "me").unwrap();
+///
+/// let matched = tree.at("/users/42").unwrap();
+/// assert_eq!(*matched.value, "user");
+/// assert_eq!(matched.params["id"], "42");
+/// ```
+pub struct RouteTree<T> {
+    root: Node,
+    routes: Vec<(String, T)>,
+}
+
+/// # RouteMatch
+///
+/// The result of a successful lookup in a `RouteTree`
+pub struct RouteMatch<'a, V> {
+    pub pattern: &'a str,
+    pub value: V,
+    pub params: HashMap<String, String>,
+}
+
+/// # RouteError
+///
+/// Returned when a pattern can not be inserted into a `RouteTree`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    /// The pattern is malformed
+    InvalidPattern { pattern: String, reason: &'static str },
+    /// The pattern is already covered by an existing, conflicting route
+    Conflict { pattern: String, existing: String },
+}
+
+impl Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::InvalidPattern { pattern, reason } => {
+                write!(f, "invalid route '{}': {}", pattern, reason)
+            }
+            RouteError::Conflict { pattern, existing } => {
+                write!(f, "route '{}' conflicts with existing route '{}'", pattern, existing)
+            }
+        }
+    }
+}
+
+impl Error for RouteError {}
+
+enum Segment<'a> {
+    Static(&'a str),
+    Param(&'a str),
+    CatchAll(&'a str),
+}
+
+#[derive(Default)]
+struct Node {
+    /// The static text of this node, or the name of the parameter for parameter and catch-all nodes
+    prefix: String,
+    /// Static children, no two of them start with the same character
+    children: Vec<Node>,
+    param: Option<Box<Node>>,
+    catch_all: Option<Box<Node>>,
+    /// Index into `RouteTree::routes` of the route ending at this node
+    route: Option<usize>,
+    /// Index of the first route that passed through this node, used for conflict messages
+    origin: usize,
+}
+
+impl<T> RouteTree<T> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::default(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// # RouteTree::insert
+    ///
+    /// Inserts a value for the given pattern
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the pattern is malformed, if it is already registered,
+    /// or if it names a parameter differently than an existing route at the same position
+    pub fn insert(&mut self, pattern: &str, value: T) -> Result<(), RouteError> {
+        let segments = parse(pattern)?;
+        let index = self.routes.len();
+
+        let conflict = |existing: usize, routes: &Vec<(String, T)>| RouteError::Conflict {
+            pattern: pattern.to_string(),
+            existing: routes[existing].0.clone(),
+        };
+
+        let mut node = &mut self.root;
+        for segment in segments {
+            node = match segment {
+                Segment::Static(text) => node.insert_static(text, index),
+                Segment::Param(name) => {
+                    let param = node.param.get_or_insert_with(|| Box::new(Node::named(name, index)));
+                    if param.prefix != name {
+                        return Err(conflict(param.origin, &self.routes));
+                    }
+                    param
+                }
+                Segment::CatchAll(name) => {
+                    let catch_all = node.catch_all.get_or_insert_with(|| Box::new(Node::named(name, index)));
+                    if catch_all.prefix != name {
+                        return Err(conflict(catch_all.origin, &self.routes));
+                    }
+                    catch_all
+                }
+            };
+        }
+
+        if let Some(existing) = node.route {
+            return Err(conflict(existing, &self.routes));
+        }
+        node.route = Some(index);
+        self.routes.push((pattern.to_string(), value));
+
+        Ok(())
+    }
+
+    /// # RouteTree::at
+    ///
+    /// Finds the route matching the given path, together with the captured parameters
+    pub fn at(&self, path: &str) -> Option<RouteMatch<'_, &T>> {
+        let mut params = Vec::new();
+        let index = self.root.find(path, &mut params)?;
+        let (pattern, value) = &self.routes[index];
+
+        Some(RouteMatch {
+            pattern,
+            value,
+            params: params.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        })
+    }
+
+    /// # RouteTree::at_mut
+    ///
+    /// Same as `RouteTree::at` but returns a mutable reference to the value
+    pub fn at_mut(&mut self, path: &str) -> Option<RouteMatch<'_, &mut T>> {
+        let mut params = Vec::new();
+        let index = self.root.find(path, &mut params)?;
+        let params = params.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        let (pattern, value) = &mut self.routes[index];
+
+        Some(RouteMatch { pattern, value, params })
+    }
+
+    /// # RouteTree::iter
+    ///
+    /// Iterates over all registered patterns and their values in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.routes.iter().map(|(pattern, value)| (pattern.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+impl<T> Default for RouteTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for RouteTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Node {
+    fn named(name: &str, origin: usize) -> Self {
+        Self {
+            prefix: name.to_string(),
+            origin,
+            ..Default::default()
+        }
+    }
+
+    fn insert_static(&mut self, text: &str, origin: usize) -> &mut Node {
+        if text.is_empty() {
+            return self;
+        }
+
+        let first = text.chars().next();
+        let index = match self.children.iter().position(|child| child.prefix.chars().next() == first) {
+            Some(index) => index,
+            None => {
+                self.children.push(Node::named(text, origin));
+                return self.children.last_mut().unwrap();
+            }
+        };
+
+        let child = &mut self.children[index];
+        let common = common_prefix(&child.prefix, text);
+
+        // Split the child if the new text diverges in the middle of its prefix
+        if common < child.prefix.len() {
+            let suffix = child.prefix.split_off(common);
+            let mut split = Node::named(&suffix, child.origin);
+            split.children = std::mem::take(&mut child.children);
+            split.param = child.param.take();
+            split.catch_all = child.catch_all.take();
+            split.route = child.route.take();
+            child.children.push(split);
+        }
+
+        child.insert_static(&text[common..], origin)
+    }
+
+    fn find<'p>(&'p self, path: &'p str, params: &mut Vec<(&'p str, &'p str)>) -> Option<usize> {
+        if path.is_empty() {
+            if let Some(route) = self.route {
+                return Some(route);
+            }
+        }
+
+        // Static children, at most one can match since they all start differently
+        for child in &self.children {
+            if let Some(rest) = path.strip_prefix(child.prefix.as_str()) {
+                if let Some(route) = child.find(rest, params) {
+                    return Some(route);
+                }
+            }
+        }
+
+        if let Some(param) = &self.param {
+            let end = path.find('/').unwrap_or(path.len());
+            if end > 0 {
+                params.push((&param.prefix, &path[..end]));
+                if let Some(route) = param.find(&path[end..], params) {
+                    return Some(route);
+                }
+                params.pop();
+            }
+        }
+
+        if let Some(catch_all) = &self.catch_all {
+            if let Some(route) = catch_all.route {
+                params.push((&catch_all.prefix, path));
+                return Some(route);
+            }
+        }
+
+        None
+    }
+}
+
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((index, _), _)| index)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+fn parse(pattern: &str) -> Result<Vec<Segment<'_>>, RouteError> {
+    let invalid = |reason| RouteError::InvalidPattern { pattern: pattern.to_string(), reason };
+
+    if !pattern.starts_with('/') {
+        return Err(invalid("patterns must start with '/'"));
+    }
+
+    let mut segments = Vec::new();
+    let mut static_start = 0;
+    let mut offset = 0;
+
+    for (i, part) in pattern.split('/').enumerate() {
+        if i > 0 {
+            offset += 1;
+        }
+        let start = offset;
+        offset += part.len();
+
+        let name = &part[1.min(part.len())..];
+        let segment = if part.starts_with(':') {
+            Segment::Param(name)
+        } else if part.starts_with('*') {
+            if offset != pattern.len() {
+                return Err(invalid("catch-all segments must be at the end of the pattern"));
+            }
+            Segment::CatchAll(name)
+        } else {
+            continue;
+        };
+
+        if name.is_empty() {
+            return Err(invalid("parameters must be named"));
+        }
+        if name.contains([':', '*']) {
+            return Err(invalid("only one parameter is allowed per segment"));
+        }
+
+        if static_start < start {
+            segments.push(Segment::Static(&pattern[static_start..start]));
+        }
+        segments.push(segment);
+        static_start = offset;
+    }
+
+    if static_start < pattern.len() {
+        segments.push(Segment::Static(&pattern[static_start..]));
+    }
+
+    Ok(segments)
+}
@@ -1,5 +1,6 @@
 use std::{
     io,
+    panic::{self, AssertUnwindSafe},
     sync::{self, Arc, Mutex},
     thread,
 };
@@ -84,7 +85,8 @@ impl Worker {
 
             match message {
                 Ok(job) => {
-                    job();
+                    // A panicking job must not take the worker down with it, the pool never replaces workers
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
                 }
                 Err(_) => {
                     // Sender has been dropped, indicating that the thread pool is shutting down
@@ -0,0 +1,282 @@
+use carola::{
+    handler::RequestHandler,
+    http::{BodyStream, Compression, HTTPRequest, HTTPResponse},
+    mime::MimeRegistry,
+};
+
+use crate::support::{send, sent_body};
+
+const LENGTH_BASE: [usize; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [usize; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u32; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+/// Reads the bits of a deflate stream, starting with the least significant bit of every byte
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn read(&mut self, count: u32) -> usize {
+        (0..count).fold(0, |value, bit| {
+            let byte = self.data[self.position / 8];
+            self.position += 1;
+            value | (((byte >> ((self.position - 1) % 8)) & 1) as usize) << bit
+        })
+    }
+
+    /// Huffman codes start with their most significant bit
+    fn read_code(&mut self, length: u32) -> usize {
+        (0..length).fold(0, |code, _| code << 1 | self.read(1))
+    }
+
+    fn read_symbol(&mut self) -> usize {
+        let code = self.read_code(7);
+        if code <= 0x17 {
+            return code + 256;
+        }
+        let code = code << 1 | self.read(1);
+        match code {
+            0x30..=0xBF => code - 0x30,
+            0xC0..=0xC7 => code - 0xC0 + 280,
+            _ => (code << 1 | self.read(1)) - 0x190 + 144,
+        }
+    }
+}
+
+/// Decompresses the stored and fixed Huffman blocks of a raw deflate stream
+fn inflate(data: &[u8]) -> Vec<u8> {
+    let mut bits = Bits { data, position: 0 };
+    let mut output = Vec::new();
+
+    loop {
+        let last = bits.read(1) == 1;
+        match bits.read(2) {
+            0 => {
+                bits.position = bits.position.div_ceil(8) * 8;
+                let length = bits.read(16);
+                bits.read(16);
+                output.extend_from_slice(&data[bits.position / 8..bits.position / 8 + length]);
+                bits.position += length * 8;
+            }
+            1 => loop {
+                let symbol = bits.read_symbol();
+                match symbol {
+                    0..=255 => output.push(symbol as u8),
+                    256 => break,
+                    _ => {
+                        let length = LENGTH_BASE[symbol - 257] + bits.read(LENGTH_EXTRA[symbol - 257]);
+                        let code = bits.read_code(5);
+                        let distance = DISTANCE_BASE[code] + bits.read(DISTANCE_EXTRA[code]);
+                        for _ in 0..length {
+                            output.push(output[output.len() - distance]);
+                        }
+                    }
+                }
+            },
+            block => panic!("Unexpected block type {}", block),
+        }
+        if last {
+            return output;
+        }
+    }
+}
+
+/// The CRC-32 of gzip computed bit by bit, independently of the table used by the encoder
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+fn get(handler: &RequestHandler, uri: &str, accept_encoding: &str) -> HTTPResponse {
+    send(handler, "GET", uri, &[&format!("Accept-Encoding: {}", accept_encoding)])
+}
+
+fn users() -> String {
+    let users = (0..200).map(|id| format!("{{\"id\":{},\"name\":\"User {}\"}}", id, id % 7)).collect::<Vec<_>>();
+    format!("[{}]", users.join(","))
+}
+
+fn handler() -> RequestHandler {
+    let mut handler = RequestHandler::new();
+    handler.set_compression(Compression::new());
+    handler.set_listener("GET", "/users", |_: HTTPRequest| HTTPResponse::json(users()));
+    handler.set_listener("GET", "/small", |_: HTTPRequest| HTTPResponse::json("[]"));
+    handler.set_listener("GET", "/binary", |_: HTTPRequest| users().into_bytes());
+    handler.set_listener("GET", "/stream", |_: HTTPRequest| {
+        let chunks = users().into_bytes().chunks(500).map(<[u8]>::to_vec).collect::<Vec<_>>();
+        HTTPResponse::builder()
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .stream(BodyStream::from_chunks(chunks))
+            .build()
+    });
+    handler.set_listener("GET", "/log", |_: HTTPRequest| {
+        HTTPResponse::builder()
+            .header("Content-Type", "text/plain")
+            .stream(BodyStream::from_chunks(log().into_iter().map(String::into_bytes)))
+            .build()
+    });
+    handler
+}
+
+/// Many small chunks, spanning several windows
+fn log() -> Vec<String> {
+    (0..5000).map(|line| format!("{} GET /users/{} 200\n", line, line * 7919 % 1000)).collect()
+}
+
+#[test]
+fn reference_values() {
+    // The check values of both checksums
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+
+    // Compressed by zlib with fixed Huffman codes, so the inflater does not only agree with the encoder
+    let reference = [
+        0xF3, 0x48, 0xCD, 0xC9, 0xC9, 0xD7, 0x51, 0xF0, 0x40, 0xA2, 0x14, 0x15, 0x42, 0x32, 0x52, 0x15, 0x0A, 0x4B, 0x33,
+        0x93, 0xB3, 0x15, 0x92, 0x8A, 0xF2, 0xCB, 0xF3, 0x14, 0xD2, 0xF2, 0x2B, 0x14, 0xB2, 0x4A, 0x73, 0x0B, 0x8A, 0x15,
+        0xF2, 0xCB, 0x52, 0x8B, 0x14, 0x4A, 0x80, 0xD2, 0x39, 0x89, 0x55, 0x95, 0x0A, 0x29, 0xF9, 0xE9, 0x3A, 0x60, 0x1E,
+        0x9A, 0x62, 0x3D, 0x00,
+    ];
+    assert_eq!(
+        inflate(&reference),
+        b"Hello, Hello, Hello! The quick brown fox jumps over the lazy dog, the quick brown fox."
+    );
+}
+
+#[test]
+fn gzip_response() {
+    let handler = handler();
+    let response = get(&handler, "/users", "gzip, deflate, br");
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+
+    let body = sent_body(response);
+    let users = users();
+    assert_eq!(body[..3], [0x1F, 0x8B, 8]);
+    assert!(body.len() < users.len() / 2);
+
+    // The trailer holds the CRC-32 and the length of the original body
+    let (deflated, trailer) = body[10..].split_at(body.len() - 18);
+    assert_eq!(inflate(deflated), users.as_bytes());
+    assert_eq!(trailer[..4], crc32(users.as_bytes()).to_le_bytes());
+    assert_eq!(trailer[4..], (users.len() as u32).to_le_bytes());
+}
+
+#[test]
+fn deflate_response() {
+    let handler = handler();
+    let response = get(&handler, "/users", "gzip;q=0.5, deflate");
+    assert_eq!(response.get_header("Content-Encoding"), Some("deflate"));
+
+    let body = sent_body(response);
+    let users = users();
+    assert_eq!(body[..2], [0x78, 0x01]);
+    assert_eq!(inflate(&body[2..body.len() - 4]), users.as_bytes());
+    assert_eq!(body[body.len() - 4..], adler32(users.as_bytes()).to_be_bytes());
+}
+
+#[test]
+fn streamed_response() {
+    let handler = handler();
+    let response = get(&handler, "/stream", "gzip");
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.get_header("Content-Length"), None);
+
+    let body = sent_body(response);
+    let users = users();
+    assert_eq!(inflate(&body[10..body.len() - 8]), users.as_bytes());
+    assert_eq!(body[body.len() - 8..body.len() - 4], crc32(users.as_bytes()).to_le_bytes());
+}
+
+#[test]
+fn many_small_chunks() {
+    let handler = handler();
+    let body = sent_body(get(&handler, "/log", "gzip"));
+    let log = log().concat();
+    assert_eq!(inflate(&body[10..body.len() - 8]), log.as_bytes());
+    assert_eq!(body[body.len() - 8..body.len() - 4], crc32(log.as_bytes()).to_le_bytes());
+}
+
+#[test]
+fn head_response() {
+    let handler = handler();
+    let compressed = sent_body(get(&handler, "/users", "gzip"));
+
+    // The same headers as the GET, without the body
+    let response = send(&handler, "HEAD", "/users", &["Accept-Encoding: gzip"]);
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(response.get_header("Content-Length"), Some(compressed.len().to_string().as_str()));
+    assert!(sent_body(response).is_empty());
+}
+
+#[test]
+fn uncompressed_responses() {
+    let handler = handler();
+
+    let response = get(&handler, "/users", "identity, gzip;q=0");
+    assert_eq!(response.get_header("Content-Encoding"), None);
+    assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(sent_body(response), users().as_bytes());
+
+    // Too small, or not a compressible type
+    assert_eq!(get(&handler, "/small", "gzip").get_header("Content-Encoding"), None);
+    assert_eq!(get(&handler, "/binary", "gzip").get_header("Content-Encoding"), None);
+
+    let mut handler = RequestHandler::new();
+    handler.set_compression(Compression::new().with_mime_types(["application/octet-stream"]).with_min_size(0));
+    handler.set_listener("GET", "/small", |_: HTTPRequest| b"[]".to_vec());
+    let response = get(&handler, "/small", "gzip");
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+}
+
+
+#[test]
+fn types_of_the_registry() {
+    let model = |_: HTTPRequest| HTTPResponse::builder().header("Content-Type", "model/gltf+json").body(users()).build();
+    let registry = || MimeRegistry::new().with_type("gltf", "model/gltf+json");
+
+    // Whether the registry is set before or after the compression
+    let mut handler = RequestHandler::new();
+    handler.set_mime_registry(registry());
+    handler.set_compression(Compression::new());
+    handler.set_listener("GET", "/model", model);
+    assert_eq!(get(&handler, "/model", "gzip").get_header("Content-Encoding"), Some("gzip"));
+
+    let mut handler = RequestHandler::new();
+    handler.set_compression(Compression::new().with_mime_type("text/x-custom"));
+    handler.set_mime_registry(registry());
+    handler.set_listener("GET", "/model", model);
+    handler.set_listener("GET", "/custom", |_: HTTPRequest| {
+        HTTPResponse::builder().header("Content-Type", "text/x-custom").body(users()).build()
+    });
+    assert_eq!(get(&handler, "/model", "gzip").get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(get(&handler, "/custom", "gzip").get_header("Content-Encoding"), Some("gzip"));
+
+    // Types listed by the configuration itself are kept
+    let mut handler = RequestHandler::new();
+    handler.set_compression(Compression::new().with_mime_types(["application/json"]));
+    handler.set_mime_registry(registry());
+    handler.set_listener("GET", "/model", model);
+    assert_eq!(get(&handler, "/model", "gzip").get_header("Content-Encoding"), None);
+}
\ No newline at end of file
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use carola::{
+    handler::RequestHandler,
+    http::{BodyStream, HTTPRequest, HTTPResponse, HTTPStatusCode},
+};
+
+/// Starts the handler on a free port and returns a connection to it
+fn connect(handler: RequestHandler) -> TcpStream {
+    let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    thread::spawn(move || handler.listen(port as usize).is_ok());
+    open(port)
+}
+
+fn open(port: u16) -> TcpStream {
+    for _ in 0..100 {
+        if let Ok(stream) = TcpStream::connect(("127.0.0.1", port)) {
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+            return stream;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("Server did not start");
+}
+
+fn handler() -> RequestHandler {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("Hello World!").into()))
+    });
+    handler
+}
+
+/// Reads a single response and returns its head and body
+fn read_response(reader: &mut BufReader<&TcpStream>) -> (String, String) {
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line == "\r\n" || line.is_empty() {
+            break;
+        }
+        head.push_str(&line);
+    }
+
+    let length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Length:"))
+        .map(|length| length.trim().parse().unwrap())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+
+    (head, String::from_utf8(body).unwrap())
+}
+
+fn is_closed(reader: &mut BufReader<&TcpStream>) -> bool {
+    matches!(reader.read(&mut [0; 1]), Ok(0))
+}
+
+#[test]
+fn keep_alive_by_default() {
+    let stream = connect(handler());
+    let mut reader = BufReader::new(&stream);
+
+    for _ in 0..3 {
+        (&stream).write_all(b"GET /example HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let (head, body) = read_response(&mut reader);
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        assert!(!head.contains("Connection:close"));
+        assert_eq!(body, "Hello World!");
+    }
+}
+
+#[test]
+fn connection_close() {
+    let stream = connect(handler());
+    let mut reader = BufReader::new(&stream);
+
+    (&stream).write_all(b"GET /example HTTP/1.1\r\nConnection: close\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(head.contains("Connection:close"));
+    assert!(is_closed(&mut reader));
+}
+
+#[test]
+fn http_1_0_opt_in() {
+    let stream = connect(handler());
+    let mut reader = BufReader::new(&stream);
+
+    (&stream).write_all(b"GET /example HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(head.contains("Connection:keep-alive"));
+
+    (&stream).write_all(b"GET /example HTTP/1.0\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(head.contains("Connection:close"));
+    assert!(is_closed(&mut reader));
+}
+
+fn streaming_handler() -> RequestHandler {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/stream", |_: HTTPRequest| {
+        let chunks = vec![b"Hello".to_vec(), b" World!".to_vec()];
+        HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), BodyStream::from_chunks(chunks))
+    });
+    handler
+}
+
+#[test]
+fn chunked_response_keeps_alive() {
+    let stream = connect(streaming_handler());
+    let mut reader = BufReader::new(&stream);
+
+    for _ in 0..2 {
+        (&stream).write_all(b"GET /stream HTTP/1.1\r\n\r\n").unwrap();
+        let (head, _) = read_response(&mut reader);
+        assert!(head.contains("Transfer-Encoding:chunked"));
+
+        let mut body = vec![0; 27];
+        reader.read_exact(&mut body).unwrap();
+        assert_eq!(body, b"5\r\nHello\r\n7\r\n World!\r\n0\r\n\r\n");
+    }
+}
+
+#[test]
+fn http_1_0_close_delimited_response() {
+    let stream = connect(streaming_handler());
+    let mut reader = BufReader::new(&stream);
+
+    (&stream).write_all(b"GET /stream HTTP/1.0\r\nConnection: keep-alive\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(!head.contains("Transfer-Encoding"));
+    assert!(head.contains("Connection:close"));
+
+    let mut body = String::new();
+    reader.read_to_string(&mut body).unwrap();
+    assert_eq!(body, "Hello World!");
+}
+
+#[test]
+fn max_requests_per_connection() {
+    let mut handler = handler();
+    handler.set_max_requests_per_connection(2);
+    let stream = connect(handler);
+    let mut reader = BufReader::new(&stream);
+
+    (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(!head.contains("Connection:close"));
+
+    (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    let (head, _) = read_response(&mut reader);
+    assert!(head.contains("Connection:close"));
+    assert!(is_closed(&mut reader));
+}
+
+#[test]
+fn idle_timeout() {
+    let mut handler = handler();
+    handler.set_keep_alive_timeout(Duration::from_millis(100));
+    let stream = connect(handler);
+    let mut reader = BufReader::new(&stream);
+
+    (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    read_response(&mut reader);
+
+    thread::sleep(Duration::from_millis(300));
+    assert!(is_closed(&mut reader));
+}
+
+
+#[test]
+fn idle_connections_do_not_block_the_pool() {
+    let first = connect(handler());
+    let port = first.peer_addr().unwrap().port();
+
+    // More idle clients than there are workers, each holding on to its connection after a response
+    let mut clients = vec![first];
+    clients.extend((0..19).map(|_| open(port)));
+    let mut kept_alive = 0;
+    for stream in &clients {
+        let start = Instant::now();
+        let mut reader = BufReader::new(stream);
+        (&*stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+        let (head, body) = read_response(&mut reader);
+        assert_eq!(body, "Hello World!");
+        assert!(start.elapsed() < Duration::from_secs(1));
+        if !head.contains("Connection:close") {
+            kept_alive += 1;
+        }
+    }
+    // A quarter of the 16 workers is kept free for new connections
+    assert_eq!(kept_alive, 12);
+
+    let start = Instant::now();
+    let stream = open(port);
+    let mut reader = BufReader::new(&stream);
+    (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(read_response(&mut reader).1, "Hello World!");
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn thread_limit() {
+    let mut handler = handler();
+    handler.set_thread_limit(2);
+    let first = connect(handler);
+    let second = open(first.peer_addr().unwrap().port());
+
+    // One of the two workers keeps its connection open, the other one closes it
+    let mut reader = BufReader::new(&first);
+    (&first).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    assert!(!read_response(&mut reader).0.contains("Connection:close"));
+
+    let mut reader = BufReader::new(&second);
+    (&second).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    assert!(read_response(&mut reader).0.contains("Connection:close"));
+    assert!(is_closed(&mut reader));
+}
+
+#[test]
+fn panicking_listener() {
+    let mut handler = handler();
+    handler.set_thread_limit(2);
+    handler.set_listener("GET", "/panic", |request: HTTPRequest| request.param("missing").unwrap().to_string());
+    let port = connect(handler).peer_addr().unwrap().port();
+
+    // More panics than workers, each one on a connection that was kept alive before
+    for _ in 0..3 {
+        let stream = open(port);
+        let mut reader = BufReader::new(&stream);
+        (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+        assert!(!read_response(&mut reader).0.contains("Connection:close"));
+
+        (&stream).write_all(b"GET /panic HTTP/1.1\r\n\r\n").unwrap();
+        let (head, _) = read_response(&mut reader);
+        assert!(head.starts_with("HTTP/1.1 500"), "{}", head);
+        assert!(head.contains("Connection:close"));
+        assert!(is_closed(&mut reader));
+    }
+
+    // The workers and the place among the kept alive connections are still there
+    let stream = open(port);
+    let mut reader = BufReader::new(&stream);
+    (&stream).write_all(b"GET /example HTTP/1.1\r\n\r\n").unwrap();
+    let (head, body) = read_response(&mut reader);
+    assert_eq!(body, "Hello World!");
+    assert!(!head.contains("Connection:close"));
+}
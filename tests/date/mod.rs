@@ -0,0 +1,25 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use carola::http::date::{format_http_date, parse_http_date};
+
+#[test]
+fn format_date() {
+    assert_eq!(format_http_date(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+    assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(784111777)), "Sun, 06 Nov 1994 08:49:37 GMT");
+    assert_eq!(format_http_date(UNIX_EPOCH + Duration::from_secs(951782400)), "Tue, 29 Feb 2000 00:00:00 GMT");
+}
+
+#[test]
+fn parse_date() {
+    let expected = Some(UNIX_EPOCH + Duration::from_secs(784111777));
+    assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"), expected);
+    assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), expected);
+    assert_eq!(parse_http_date("Sun Nov  6 08:49:37 1994"), expected);
+
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    assert_eq!(parse_http_date(&format_http_date(now)), Some(now));
+
+    for invalid in ["", "Sun, 06 Nov 1994 08:49:37 UTC", "Sun, 32 Nov 1994 08:49:37 GMT", "Sun, 06 Foo 1994 08:49:37 GMT", "Sun, 06 Nov 1994 08:49 GMT"] {
+        assert_eq!(parse_http_date(invalid), None, "{}", invalid);
+    }
+}
@@ -0,0 +1,16 @@
+use carola::http::encoding::quality;
+
+#[test]
+fn encoding_quality() {
+    assert_eq!(quality(Some("gzip, br;q=0.5"), "gzip"), 1.0);
+    assert_eq!(quality(Some("gzip, br;q=0.5"), "br"), 0.5);
+    assert_eq!(quality(Some("gzip, br;q=0.5"), "deflate"), 0.0);
+    assert_eq!(quality(Some("x-gzip"), "gzip"), 1.0);
+    assert_eq!(quality(Some("*;q=0.2, br;q=0"), "gzip"), 0.2);
+    assert_eq!(quality(Some("*;q=0.2, br;q=0"), "br"), 0.0);
+
+    assert_eq!(quality(Some("gzip"), "identity"), 1.0);
+    assert_eq!(quality(Some("identity;q=0"), "identity"), 0.0);
+    assert_eq!(quality(None, "identity"), 1.0);
+    assert_eq!(quality(None, "gzip"), 0.0);
+}
@@ -0,0 +1,6 @@
+mod compression;
+mod connection;
+mod mime_registry;
+mod public_folder;
+mod routing;
+mod support;
\ No newline at end of file
@@ -0,0 +1,65 @@
+use carola::http::{HeaderError, HeaderMap};
+
+#[test]
+fn case_insensitive_names() {
+    let mut headers = HeaderMap::new();
+    headers.insert("Content-Type", "text/html");
+
+    assert_eq!(headers.get("content-type"), Some("text/html"));
+    assert!(headers.contains_key("CONTENT-TYPE"));
+
+    headers.insert("content-type", "text/plain");
+    assert_eq!(headers.len(), 1);
+    assert_eq!(headers["Content-Type"], "text/plain");
+}
+
+#[test]
+fn multiple_values() {
+    let mut headers = HeaderMap::new();
+    headers.append("Set-Cookie", "theme=dark");
+    headers.append("Cache-Control", "no-store");
+    headers.append("set-cookie", "lang=en");
+
+    assert_eq!(headers.get("Set-Cookie"), Some("theme=dark"));
+    assert_eq!(headers.get_all("Set-Cookie").collect::<Vec<_>>(), vec!["theme=dark", "lang=en"]);
+
+    headers.insert("Set-Cookie", "session=1");
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        vec![("Set-Cookie", "session=1"), ("Cache-Control", "no-store")]
+    );
+
+    assert_eq!(headers.remove("set-cookie"), Some(String::from("session=1")));
+    assert_eq!(headers.remove("Set-Cookie"), None);
+    assert_eq!(headers.len(), 1);
+}
+
+#[test]
+fn insertion_order() {
+    let headers = ["X-C", "X-A", "X-B"]
+        .into_iter()
+        .map(|name| (name, "1"))
+        .collect::<HeaderMap>();
+
+    assert_eq!(headers.iter().map(|(name, _)| name).collect::<Vec<_>>(), vec!["X-C", "X-A", "X-B"]);
+}
+
+#[test]
+fn validation() {
+    let mut headers = HeaderMap::new();
+
+    assert_eq!(headers.try_insert("", "value"), Err(HeaderError::InvalidName(String::new())));
+    assert!(matches!(headers.try_insert("Bad Name", "value"), Err(HeaderError::InvalidName(_))));
+    assert!(matches!(headers.try_append("X-Name:", "value"), Err(HeaderError::InvalidName(_))));
+    assert!(matches!(headers.try_insert("X-Injected", "a\r\nSet-Cookie: b"), Err(HeaderError::InvalidValue(_))));
+    assert!(headers.is_empty());
+
+    assert!(headers.try_insert("X-Tab", "a\tb").is_ok());
+    assert!(headers.try_insert("X-Unicode", "café").is_ok());
+}
+
+#[test]
+#[should_panic]
+fn insert_invalid_header() {
+    HeaderMap::new().insert("X-Injected", "a\nb");
+}
@@ -1,2 +1,5 @@
+mod date;
+mod encoding;
+mod header_map;
 mod http_request;
 mod http_response;
\ No newline at end of file
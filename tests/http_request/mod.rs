@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use carola::http::{self, HTTPMethod};
+use carola::http::{self, HTTPMethod, HTTPStatusCode, HeaderMap};
 
 #[test]
 fn request_without_content() {
@@ -20,7 +18,7 @@ fn request_with_content() {
         "Host: reqbin.com",
         "Accept: application/json",
         "Content-Type: application/json",
-        "Content-Length: 81",
+        "Content-Length: 78",
         "",
         "{",
         "\"Id\": 78912,",
@@ -30,7 +28,11 @@ fn request_with_content() {
         "}",
     ];
 
-    http::HTTPRequest::parse(raw).expect("Failed to parse");
+    let parsed = http::HTTPRequest::parse(raw).expect("Failed to parse");
+    assert_eq!(
+        parsed.get_body_str(),
+        Some("{\r\n\"Id\": 78912,\r\n\"Customer\": \"Jason Sweet\",\r\n\"Quantity\": 1,\r\n\"Price\": 18.00\r\n}")
+    );
 }
 
 #[test]
@@ -56,9 +58,163 @@ fn headers() {
     ];
 
     let parsed = http::HTTPRequest::parse(raw).expect("Failed to parse");
-    let mut expected = HashMap::new();
-    expected.insert("Host".to_string(), "localhost:8000".to_string());
-    expected.insert("Accept".to_string(), "application/json".to_string());
+    let mut expected = HeaderMap::new();
+    expected.insert("Host", "localhost:8000");
+    expected.insert("Accept", "application/json");
 
     assert_eq!(*parsed.get_headers(), expected);
 }
+
+#[test]
+fn query_string() {
+    let raw = vec![
+        "GET /search%20results?q=hello+world&tag=a&tag=b%26c&page=2&empty HTTP/1.1",
+        "Host: localhost:8000",
+    ];
+
+    let parsed = http::HTTPRequest::parse(raw).expect("Failed to parse");
+    assert_eq!(parsed.get_uri(), "/search%20results?q=hello+world&tag=a&tag=b%26c&page=2&empty");
+    assert_eq!(parsed.get_path(), "/search results");
+    assert_eq!(parsed.query("q"), Some("hello world"));
+    assert_eq!(parsed.query_all("tag").collect::<Vec<_>>(), vec!["a", "b&c"]);
+    assert_eq!(parsed.query_as::<u32>("page"), Some(Ok(2)));
+    assert!(parsed.query_as::<u32>("q").unwrap().is_err());
+    assert_eq!(parsed.query("empty"), Some(""));
+    assert_eq!(parsed.query("missing"), None);
+}
+
+#[test]
+fn invalid_percent_encoding() {
+    let raw = vec!["GET /%zz HTTP/1.1", "Host: localhost:8000"];
+
+    assert!(http::HTTPRequest::parse(raw).is_err());
+}
+
+#[test]
+fn invalid_target_characters() {
+    for target in ["/docs?a=\x01", "/a\x7Fb", "/caf\u{e9}", "/a\x0Bb"] {
+        let raw = vec![format!("GET {} HTTP/1.1", target)];
+
+        let err = http::HTTPRequest::parse(raw).unwrap_err();
+        assert_eq!(*err.get_status(), HTTPStatusCode::BadRequest, "{:?}", target);
+    }
+}
+
+#[test]
+fn absolute_form_target() {
+    let raw = vec!["GET http://localhost:8000/example?x=1 HTTP/1.1", "Host: localhost:8000"];
+
+    let parsed = http::HTTPRequest::parse(raw).expect("Failed to parse");
+    assert_eq!(parsed.get_path(), "/example");
+    assert_eq!(parsed.query("x"), Some("1"));
+}
+
+#[test]
+fn binary_body() {
+    let mut raw = b"POST /upload HTTP/1.1\r\nContent-Length: 6\r\n\r\n\x89PNG\r\n".to_vec();
+    raw.extend_from_slice(b"GET /next HTTP/1.1\r\n\r\n");
+    let mut reader = raw.as_slice();
+
+    let parsed = http::HTTPRequest::read(&mut reader, 1024).expect("Failed to parse");
+    assert_eq!(parsed.get_body().as_deref(), Some(&b"\x89PNG\r\n"[..]));
+
+    // The next request on the same stream is left untouched
+    let next = http::HTTPRequest::read(&mut reader, 1024).expect("Failed to parse");
+    assert_eq!(next.get_path(), "/next");
+}
+
+#[test]
+fn body_without_trailing_newline() {
+    let mut reader = &b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello"[..];
+
+    let parsed = http::HTTPRequest::read(&mut reader, 1024).expect("Failed to parse");
+    assert_eq!(parsed.get_body_str(), Some("hello"));
+}
+
+#[test]
+fn invalid_bodies() {
+    let mut short = &b"POST /echo HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello"[..];
+    let err = http::HTTPRequest::read(&mut short, 1024).unwrap_err();
+    assert_eq!(*err.get_status(), HTTPStatusCode::BadRequest);
+
+    let mut large = &b"POST /echo HTTP/1.1\r\nContent-Length: 2048\r\n\r\nhello"[..];
+    let err = http::HTTPRequest::read(&mut large, 1024).unwrap_err();
+    assert_eq!(*err.get_status(), HTTPStatusCode::PayloadTooLarge);
+
+    let mut conflicting = &b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\ncontent-length: 6\r\n\r\nhello!"[..];
+    let err = http::HTTPRequest::read(&mut conflicting, 1024).unwrap_err();
+    assert_eq!(*err.get_status(), HTTPStatusCode::BadRequest);
+}
+
+#[test]
+fn chunked_body() {
+    let mut reader = &b"POST /upload HTTP/1.1\r\n\
+        Transfer-Encoding: chunked\r\n\
+        \r\n\
+        5;name=value\r\n\
+        hello\r\n\
+        7\r\n\
+        , world\r\n\
+        0\r\n\
+        Checksum: abc\r\n\
+        \r\n\
+        GET /next HTTP/1.1\r\n\r\n"[..];
+
+    let parsed = http::HTTPRequest::read(&mut reader, 1024).expect("Failed to parse");
+    assert_eq!(parsed.get_body_str(), Some("hello, world"));
+    assert_eq!(parsed.get_trailers()["Checksum"], "abc");
+
+    let next = http::HTTPRequest::read(&mut reader, 1024).expect("Failed to parse");
+    assert_eq!(next.get_path(), "/next");
+}
+
+#[test]
+fn invalid_chunked_bodies() {
+    let cases: [(&[u8], HTTPStatusCode); 6] = [
+        // Both framing headers
+        (b"POST / HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n", HTTPStatusCode::BadRequest),
+        // Chunked is not the final coding
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n0\r\n\r\n", HTTPStatusCode::BadRequest),
+        // Invalid chunk size
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\nhello\r\n0\r\n\r\n", HTTPStatusCode::BadRequest),
+        // Missing CRLF after the chunk data
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nhello\r\n0\r\n\r\n", HTTPStatusCode::BadRequest),
+        // Missing last chunk
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n", HTTPStatusCode::BadRequest),
+        // Larger than the limit
+        (b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n800\r\n", HTTPStatusCode::PayloadTooLarge),
+    ];
+
+    for (raw, status) in cases {
+        let mut reader = raw;
+        let err = http::HTTPRequest::read(&mut reader, 1024).unwrap_err();
+        assert_eq!(*err.get_status(), status, "{}", String::from_utf8_lossy(raw));
+    }
+}
+
+#[test]
+fn repeated_headers() {
+    let raw = vec![
+        "POST / HTTP/1.1",
+        "Accept: text/html",
+        "accept: application/json",
+        "content-length: 2",
+        "",
+        "ok",
+    ];
+
+    let parsed = http::HTTPRequest::parse(raw).expect("Failed to parse");
+    assert_eq!(
+        parsed.get_headers().get_all("Accept").collect::<Vec<_>>(),
+        vec!["text/html", "application/json"]
+    );
+    assert_eq!(parsed.get_body_str(), Some("ok"));
+}
+
+#[test]
+fn invalid_header_name() {
+    let raw = vec!["GET / HTTP/1.1", "X(Bad): value"];
+
+    let err = http::HTTPRequest::parse(raw).unwrap_err();
+    assert_eq!(*err.get_status(), HTTPStatusCode::BadRequest);
+}
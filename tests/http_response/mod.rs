@@ -1,24 +1,175 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, thread};
 
-use carola::http::{HTTPResponse, HTTPStatusCode};
+use carola::http::{BodyStream, HTTPResponse, HTTPStatusCode, IntoResponse};
 
 #[test]
 fn construct_response() {
     let headers = HashMap::new();
     let body = "<html><body><h1>Hello, World!</h1></body></html>".to_string();
 
-    let response = HTTPResponse::new("1.1", HTTPStatusCode::OK, headers, Some(body.clone()));
+    let response = HTTPResponse::new("1.1", HTTPStatusCode::OK, headers, Some(body.clone().into()));
     let constructed = response.construct();
 
     assert_eq!(
-        constructed,
+        String::from_utf8(constructed).unwrap(),
         format!(
             "HTTP/1.1 200 OK\r\n\
             Content-Length:{}\r\n\
             \r\n\
-            <html><body><h1>Hello, World!</h1></body></html>\r\n\
-            \r\n",
+            <html><body><h1>Hello, World!</h1></body></html>",
             body.len()
         )
     )
 }
+
+#[test]
+fn construct_binary_response() {
+    let body = vec![0x89, b'P', b'N', b'G', 0x00, 0xff];
+
+    let response = HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(body.clone()));
+    let constructed = response.construct();
+
+    let mut expected = b"HTTP/1.1 200 OK\r\nContent-Length:6\r\n\r\n".to_vec();
+    expected.extend(body);
+    assert_eq!(constructed, expected);
+}
+
+#[test]
+fn streamed_response_with_length() {
+    let stream = BodyStream::from_reader(&b"Hello World!"[..], Some(12));
+
+    let response = HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), stream);
+    assert_eq!(response.construct(), b"HTTP/1.1 200 OK\r\nContent-Length:12\r\n\r\nHello World!");
+}
+
+#[test]
+fn streamed_response_without_length() {
+    let chunks = vec![b"Hello".to_vec(), Vec::new(), b" World!".to_vec()];
+    let stream = BodyStream::from_chunks(chunks);
+
+    let response = HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), stream);
+    assert_eq!(
+        response.construct(),
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding:chunked\r\n\r\n5\r\nHello\r\n7\r\n World!\r\n0\r\n\r\n"
+    );
+}
+
+#[test]
+fn streamed_response_length_mismatch() {
+    let stream = BodyStream::from_reader(&b"Hello"[..], Some(12));
+
+    let response = HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), stream);
+    assert!(response.write_to(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn streamed_response_with_trailers() {
+    let stream = BodyStream::from_chunks(vec![b"Hello".to_vec()]).with_trailer("X-Checksum", "abc");
+
+    let response = HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), stream);
+    let response = String::from_utf8(response.construct()).unwrap();
+    assert!(response.contains("\r\nTrailer:X-Checksum\r\n"));
+    assert!(response.ends_with("\r\n\r\n5\r\nHello\r\n0\r\nX-Checksum:abc\r\n\r\n"));
+}
+
+#[test]
+fn channel_response() {
+    let (sender, stream) = BodyStream::channel();
+    let producer = thread::spawn(move || {
+        sender.send("Hello").unwrap();
+        sender.send(" World!").unwrap();
+        sender.trailer("X-Count", "2").unwrap();
+    });
+
+    let response = HTTPResponse::new_streaming("1.1", HTTPStatusCode::OK, HashMap::new(), stream);
+    assert_eq!(
+        response.construct(),
+        b"HTTP/1.1 200 OK\r\nTransfer-Encoding:chunked\r\n\r\n5\r\nHello\r\n7\r\n World!\r\n0\r\nX-Count:2\r\n\r\n"
+    );
+    producer.join().unwrap();
+}
+
+#[test]
+fn channel_sender_after_body_dropped() {
+    let (sender, stream) = BodyStream::channel();
+    drop(stream);
+
+    assert!(sender.send("Hello").is_err());
+}
+
+#[test]
+fn response_builder() {
+    let response = HTTPResponse::builder()
+        .status(HTTPStatusCode::Created)
+        .header("Location", "/users/1")
+        .body("Created")
+        .build();
+
+    assert_eq!(*response.get_status(), HTTPStatusCode::Created);
+    assert_eq!(response.get_version(), "1.1");
+    assert_eq!(response.get_header("location"), Some("/users/1"));
+    assert_eq!(response.get_body_str(), Some("Created"));
+}
+
+#[test]
+fn convenience_constructors() {
+    let response = HTTPResponse::text("Hello World!");
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(response.get_header("Content-Type"), Some("text/plain; charset=utf-8"));
+    assert_eq!(response.get_body_str(), Some("Hello World!"));
+
+    let response = HTTPResponse::html("<h1>Hello</h1>");
+    assert_eq!(response.get_header("Content-Type"), Some("text/html; charset=utf-8"));
+
+    let response = HTTPResponse::json(r#"{"hello":"world"}"#);
+    assert_eq!(response.get_header("Content-Type"), Some("application/json"));
+    assert_eq!(response.get_body_str(), Some(r#"{"hello":"world"}"#));
+
+    let response = HTTPResponse::redirect("/login");
+    assert_eq!(*response.get_status(), HTTPStatusCode::Found);
+    assert_eq!(response.get_header("Location"), Some("/login"));
+    assert!(!response.has_body());
+
+    let response = HTTPResponse::no_content();
+    assert_eq!(*response.get_status(), HTTPStatusCode::NoContent);
+    assert!(!response.has_body());
+
+    let response = HTTPResponse::error(HTTPStatusCode::Forbidden);
+    assert_eq!(*response.get_status(), HTTPStatusCode::Forbidden);
+    assert!(response.get_body_str().unwrap().contains("403 Forbidden"));
+}
+
+#[test]
+fn into_response() {
+    let response = b"binary".to_vec().into_response();
+    assert_eq!(response.get_header("Content-Type"), Some("application/octet-stream"));
+    assert_eq!(response.get_body(), &Some(b"binary".to_vec()));
+
+    let response = (HTTPStatusCode::Accepted, "Queued").into_response();
+    assert_eq!(*response.get_status(), HTTPStatusCode::Accepted);
+    assert_eq!(response.get_body_str(), Some("Queued"));
+
+    let response = ([("Cache-Control", "no-store")], HTTPResponse::no_content()).into_response();
+    assert_eq!(*response.get_status(), HTTPStatusCode::NoContent);
+    assert_eq!(response.get_header("Cache-Control"), Some("no-store"));
+
+    let response = None::<String>.into_response();
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotFound);
+
+    let response = Err::<String, _>(HTTPResponse::error(HTTPStatusCode::Conflict)).into_response();
+    assert_eq!(*response.get_status(), HTTPStatusCode::Conflict);
+}
+
+#[test]
+fn repeated_response_headers() {
+    let response = HTTPResponse::builder()
+        .header("X-First", "1")
+        .append_header("Set-Cookie", "theme=dark")
+        .append_header("Set-Cookie", "lang=en")
+        .build();
+
+    assert_eq!(
+        String::from_utf8(response.construct()).unwrap(),
+        "HTTP/1.1 200 OK\r\nX-First:1\r\nSet-Cookie:theme=dark\r\nSet-Cookie:lang=en\r\n\r\n"
+    );
+}
@@ -0,0 +1,64 @@
+use std::{env, fs, path::Path, process};
+
+use carola::{
+    handler::RequestHandler,
+    http::HTTPRequest,
+    mime::{sniff, MimeRegistry},
+};
+
+#[test]
+fn default_types() {
+    let registry = MimeRegistry::new();
+    assert_eq!(registry.get("woff2"), Some("font/woff2"));
+    assert_eq!(registry.get("PNG"), Some("image/png"));
+    assert_eq!(registry.get(".webp"), Some("image/webp"));
+    assert_eq!(registry.get("unknown"), None);
+
+    assert_eq!(registry.content_type(Path::new("style.css")), "text/css; charset=utf-8");
+    assert_eq!(registry.content_type(Path::new("data.json")), "application/json");
+    assert_eq!(registry.content_type(Path::new("archive.unknown")), "application/octet-stream");
+    assert_eq!(registry.content_type(Path::new("Makefile")), "application/octet-stream");
+}
+
+#[test]
+fn custom_types() {
+    let mut registry = MimeRegistry::new().with_type("gltf", "model/gltf+json").with_type("txt", "text/plain; charset=iso-8859-1");
+    registry.add_types("# Comment\n\napplication/x-custom  cst   CST2\ntext/x-rust rs\n");
+
+    assert_eq!(registry.get("gltf"), Some("model/gltf+json"));
+    assert_eq!(registry.get("cst2"), Some("application/x-custom"));
+    assert_eq!(registry.content_type(Path::new("main.rs")), "text/x-rust; charset=utf-8");
+    assert_eq!(registry.content_type(Path::new("notes.txt")), "text/plain; charset=iso-8859-1");
+}
+
+#[test]
+fn types_file() {
+    let path = env::temp_dir().join(format!("carola-mime-types-{}", process::id()));
+    fs::write(&path, "model/gltf-binary\tglb\n").unwrap();
+
+    let registry = MimeRegistry::new().with_types_file(&path).unwrap();
+    assert_eq!(registry.get("glb"), Some("model/gltf-binary"));
+    assert!(MimeRegistry::new().with_types_file(path.with_extension("missing")).is_err());
+}
+
+#[test]
+fn sniffing() {
+    assert_eq!(sniff(b"\x89PNG\r\n\x1A\n\x00\x00"), "image/png");
+    assert_eq!(sniff(b"RIFF\x00\x00\x00\x00WEBPVP8 "), "image/webp");
+    assert_eq!(sniff(b"\x00\x00\x00\x18ftypmp42"), "video/mp4");
+    assert_eq!(sniff(b"  <!DOCTYPE html><html>"), "text/html");
+    assert_eq!(sniff("Grüße\n".as_bytes()), "text/plain");
+    assert_eq!(sniff(b"\x00\x01\x02\x03"), "application/octet-stream");
+
+    let base = env::temp_dir().join(format!("carola-sniffing-{}", process::id()));
+    fs::create_dir_all(&base).unwrap();
+    fs::write(base.join("LICENSE"), "MIT License").unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.clone());
+    let get = |handler: &RequestHandler| handler.match_request(HTTPRequest::parse(vec!["GET /LICENSE HTTP/1.1"]).unwrap());
+    assert_eq!(get(&handler).get_header("Content-Type"), Some("application/octet-stream"));
+
+    handler.set_mime_registry(MimeRegistry::new().with_sniffing(true));
+    assert_eq!(get(&handler).get_header("Content-Type"), Some("text/plain; charset=utf-8"));
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use carola::{
+    handler::RequestHandler,
+    http::{HTTPRequest, HTTPResponse, HTTPStatusCode},
+    router::Router,
+};
+
+use crate::support::{body, request};
+
+fn text(body: &str) -> HTTPResponse {
+    HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(body.to_string().into()))
+}
+
+fn api() -> Router {
+    let mut users = Router::new();
+    users.set_listener("GET", "/:id", |request: HTTPRequest| {
+        text(&format!("{} {}", request.get_path(), request.param("id").unwrap()))
+    });
+
+    let mut api = Router::new();
+    api.set_listener("GET", "/", |_: HTTPRequest| text("index"));
+    api.mount("/users", users);
+    api
+}
+
+#[test]
+fn router_on_its_own() {
+    let router = api();
+
+    assert_eq!(body(&router.handle(request("GET", "/"))), "index");
+    assert_eq!(body(&router.handle(request("GET", "/users/42"))), "/users/42 42");
+    assert_eq!(*router.handle(request("GET", "/posts")).get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn mounted_on_handler() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/api/v1/status", |_: HTTPRequest| text("status"));
+    handler.mount("/api/v1/", api());
+
+    assert_eq!(body(&handler.match_request(request("GET", "/api/v1"))), "index");
+    assert_eq!(body(&handler.match_request(request("GET", "/api/v1/users/7"))), "/api/v1/users/7 7");
+    assert_eq!(body(&handler.match_request(request("GET", "/api/v1/status"))), "status");
+    assert_eq!(*handler.match_request(request("GET", "/api/v1x")).get_status(), HTTPStatusCode::NotFound);
+
+    let response = handler.match_request(request("POST", "/api/v1/users/7"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::MethodNotAllowed);
+    assert_eq!(response.get_headers()["Allow"], "GET, HEAD, OPTIONS");
+}
+
+#[test]
+fn mounted_not_found_callback() {
+    let mut admin = Router::new();
+    admin.set_listener("GET", "/stats", |_: HTTPRequest| text("stats"));
+    admin.set_not_found_callback(|_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::NotFound, HashMap::new(), Some(String::from("admin 404").into()))
+    });
+
+    let mut handler = RequestHandler::new();
+    handler.mount("/admin", admin);
+
+    assert_eq!(body(&handler.match_request(request("GET", "/admin/stats"))), "stats");
+    assert_eq!(body(&handler.match_request(request("GET", "/admin/missing"))), "admin 404");
+    assert_ne!(body(&handler.match_request(request("GET", "/missing"))), "admin 404");
+}
@@ -0,0 +1,460 @@
+use std::{env, fs, path::PathBuf, process, time::Duration};
+
+use carola::{
+    handler::RequestHandler,
+    http::{date::format_http_date, Compression, HTTPRequest, HTTPStatusCode},
+    public_folder::{DotfilePolicy, PublicFolder, SymlinkPolicy},
+};
+
+use crate::support::{fetch, send, sent_body, sent_text};
+
+/// Creates a fresh folder with a public folder inside of it and a secret file next to it
+fn fixture(name: &str) -> PathBuf {
+    let base = env::temp_dir().join(format!("carola-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&base);
+
+    fs::create_dir_all(base.join("public/docs")).unwrap();
+    fs::write(base.join("public/index.html"), "<h1>Home</h1>").unwrap();
+    fs::write(base.join("public/docs/guide.txt"), "Guide").unwrap();
+    fs::write(base.join("public/.env"), "SECRET=1").unwrap();
+    fs::write(base.join("secret.txt"), "Secret").unwrap();
+    base
+}
+
+#[test]
+fn serves_files() {
+    let base = fixture("serves-files");
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = fetch(&handler, "/docs/guide.txt");
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(sent_text(response), "Guide");
+
+    assert_eq!(sent_text(fetch(&handler, "/docs/./../docs/guide%2Etxt")), "Guide");
+
+    assert_eq!(*fetch(&handler, "/missing.txt").get_status(), HTTPStatusCode::NotFound);
+    assert_eq!(*fetch(&handler, "/docs/").get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn file_methods() {
+    let base = fixture("file-methods");
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = send(&handler, "OPTIONS", "/index.html", &[]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::NoContent);
+    assert_eq!(response.get_header("Allow"), Some("GET, HEAD, OPTIONS"));
+
+    for method in ["POST", "PUT", "DELETE", "PATCH"] {
+        let response = send(&handler, method, "/index.html", &["Content-Length: 0"]);
+        assert_eq!(*response.get_status(), HTTPStatusCode::MethodNotAllowed, "{}", method);
+        assert_eq!(response.get_header("Allow"), Some("GET, HEAD, OPTIONS"));
+        assert!(!sent_text(response).contains("Home"));
+    }
+
+    // Directories and missing files are treated the same way as for GET
+    assert_eq!(*send(&handler, "POST", "/", &[]).get_status(), HTTPStatusCode::MethodNotAllowed);
+    assert_eq!(*send(&handler, "POST", "/docs/", &[]).get_status(), HTTPStatusCode::NotFound);
+    assert_eq!(*send(&handler, "POST", "/missing.txt", &[]).get_status(), HTTPStatusCode::NotFound);
+    assert_eq!(*send(&handler, "OPTIONS", "/missing.txt", &[]).get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn path_traversal() {
+    let base = fixture("path-traversal");
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    for uri in ["/../secret.txt", "/docs/../../secret.txt", "/%2E%2E/secret.txt", "/..%2Fsecret.txt", "/..\\secret.txt"] {
+        let response = fetch(&handler, uri);
+        assert_ne!(*response.get_status(), HTTPStatusCode::OK, "{}", uri);
+        assert!(!sent_text(response).contains("Secret"), "{}", uri);
+    }
+
+    let absolute = base.join("secret.txt");
+    let response = fetch(&handler, &format!("/{}", absolute.display()));
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn dotfiles() {
+    let base = fixture("dotfiles");
+    let mut handler = RequestHandler::new();
+
+    handler.set_public_folder(base.join("public"));
+    assert_eq!(*fetch(&handler, "/.env").get_status(), HTTPStatusCode::NotFound);
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_dotfile_policy(DotfilePolicy::Deny));
+    assert_eq!(*fetch(&handler, "/.env").get_status(), HTTPStatusCode::Forbidden);
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_dotfile_policy(DotfilePolicy::Allow));
+    assert_eq!(sent_text(fetch(&handler, "/.env")), "SECRET=1");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks() {
+    let base = fixture("symlinks");
+    std::os::unix::fs::symlink(base.join("secret.txt"), base.join("public/outside.txt")).unwrap();
+    std::os::unix::fs::symlink(base.join("public/docs/guide.txt"), base.join("public/inside.txt")).unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+    assert_eq!(*fetch(&handler, "/outside.txt").get_status(), HTTPStatusCode::Forbidden);
+    assert_eq!(sent_text(fetch(&handler, "/inside.txt")), "Guide");
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_symlink_policy(SymlinkPolicy::Deny));
+    assert_eq!(*fetch(&handler, "/inside.txt").get_status(), HTTPStatusCode::Forbidden);
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_symlink_policy(SymlinkPolicy::Follow));
+    assert_eq!(sent_text(fetch(&handler, "/outside.txt")), "Secret");
+}
+
+#[cfg(unix)]
+#[test]
+fn listing_symlinks() {
+    let base = fixture("listing-symlinks");
+    fs::remove_file(base.join("public/index.html")).unwrap();
+    std::os::unix::fs::symlink(base.join("secret.txt"), base.join("public/outside.txt")).unwrap();
+    std::os::unix::fs::symlink(base.join("public/docs/guide.txt"), base.join("public/inside.txt")).unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_listing(true));
+    let listing = sent_text(fetch(&handler, "/"));
+    assert!(!listing.contains("outside.txt"));
+    assert!(listing.contains("inside.txt"));
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_listing(true).with_symlink_policy(SymlinkPolicy::Deny));
+    let listing = sent_text(fetch(&handler, "/"));
+    assert!(!listing.contains("outside.txt"));
+    assert!(!listing.contains("inside.txt"));
+    assert!(listing.contains("docs/"));
+}
+
+#[test]
+fn directory_index() {
+    let base = fixture("directory-index");
+    fs::write(base.join("public/docs/start.html"), "<h1>Start</h1>").unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+    assert_eq!(sent_text(fetch(&handler, "/")), "<h1>Home</h1>");
+
+    let response = fetch(&handler, "/docs?page=2");
+    assert_eq!(*response.get_status(), HTTPStatusCode::MovedPermanently);
+    assert_eq!(response.get_header("Location"), Some("/docs/?page=2"));
+
+    // The location is built from the normalized path, it can neither point to another host nor contain control characters
+    assert_eq!(fetch(&handler, "//docs").get_header("Location"), Some("/docs/"));
+    assert_eq!(fetch(&handler, "/docs?a=%01&b=x%20y").get_header("Location"), Some("/docs/?a=%01&b=x%20y"));
+    assert!(HTTPRequest::parse(vec!["GET /docs?a=\x01 HTTP/1.1"]).is_err());
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_index_file(Some("start.html")));
+    assert_eq!(sent_text(fetch(&handler, "/docs/")), "<h1>Start</h1>");
+
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_index_file(None));
+    assert_eq!(*fetch(&handler, "/").get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn directory_listing() {
+    let base = fixture("directory-listing");
+    fs::create_dir(base.join("public/docs/a <b>")).unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_listing(true));
+
+    let response = fetch(&handler, "/docs/");
+    let listing = response.get_body_str().unwrap();
+    assert_eq!(response.get_header("Content-Type"), Some("text/html; charset=utf-8"));
+    assert!(listing.contains("<a href=\"a%20%3Cb%3E/\">a &lt;b&gt;/</a>"));
+    assert!(listing.contains("<a href=\"guide.txt\">guide.txt</a></td><td>5</td>"));
+    assert!(listing.find("a &lt;b&gt;").unwrap() < listing.find("guide.txt").unwrap());
+
+    let request = HTTPRequest::parse(vec!["GET /docs/ HTTP/1.1", "Accept: application/json"]).unwrap();
+    let response = handler.match_request(request);
+    let listing = response.get_body_str().unwrap();
+    assert_eq!(response.get_header("Content-Type"), Some("application/json"));
+    assert!(listing.starts_with("[{\"name\":\"a <b>\",\"type\":\"directory\",\"size\":0,\"modified\":"));
+    assert!(listing.contains("{\"name\":\"guide.txt\",\"type\":\"file\",\"size\":5,\"modified\":"));
+
+    // The format with the higher quality wins, html if neither is preferred
+    let content_type = |accept: &str| {
+        let response = send(&handler, "GET", "/docs/", &[accept]);
+        response.get_header("Content-Type").unwrap_or_default().to_string()
+    };
+    assert_eq!(content_type("Accept: application/json, text/html;q=0"), "application/json");
+    assert_eq!(content_type("Accept: text/html;q=0.5, application/json;q=0.9"), "application/json");
+    assert_eq!(content_type("Accept: application/json;q=0.5, text/html"), "text/html; charset=utf-8");
+    assert_eq!(content_type("Accept: application/json, text/html"), "text/html; charset=utf-8");
+    assert_eq!(content_type("Accept: application/json;q=0"), "text/html; charset=utf-8");
+    assert_eq!(content_type("Accept: */*"), "text/html; charset=utf-8");
+
+    // The index file still wins over the listing, and dotfiles are hidden
+    assert_eq!(sent_text(fetch(&handler, "/")), "<h1>Home</h1>");
+    fs::remove_file(base.join("public/index.html")).unwrap();
+    assert!(!sent_text(fetch(&handler, "/")).contains(".env"));
+}
+
+#[test]
+fn single_range() {
+    let base = fixture("single-range");
+    fs::write(base.join("public/numbers.txt"), "0123456789").unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = fetch(&handler, "/numbers.txt");
+    assert_eq!(response.get_header("Accept-Ranges"), Some("bytes"));
+
+    let cases = [("bytes=2-4", "234", "bytes 2-4/10"), ("bytes=7-", "789", "bytes 7-9/10"), ("bytes=-2", "89", "bytes 8-9/10"), ("bytes=8-100", "89", "bytes 8-9/10")];
+    for (range, expected, content_range) in cases {
+        let response = send(&handler, "GET", "/numbers.txt", &[&format!("Range: {}", range)]);
+        assert_eq!(*response.get_status(), HTTPStatusCode::PartialContent, "{}", range);
+        assert_eq!(response.get_header("Content-Range"), Some(content_range));
+        assert_eq!(sent_text(response), expected);
+    }
+
+    // Invalid ranges are ignored
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=5-2"]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(sent_text(response), "0123456789");
+}
+
+#[test]
+fn multiple_ranges() {
+    let base = fixture("multiple-ranges");
+    fs::write(base.join("public/numbers.txt"), "0123456789").unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=0-1, 5-6"]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::PartialContent);
+    let content_type = response.get_header("Content-Type").unwrap().to_string();
+    let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap().to_string();
+
+    let length = response.get_stream().as_ref().unwrap().get_length();
+    let body = sent_text(response);
+    assert_eq!(length, Some(body.len() as u64));
+    assert_eq!(
+        body,
+        format!(
+            "--{0}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+            --{0}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Range: bytes 5-6/10\r\n\r\n56\r\n\
+            --{0}--\r\n",
+            boundary
+        )
+    );
+
+    // Overlapping ranges are merged into one
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=0-3, 2-5"]);
+    assert_eq!(response.get_header("Content-Range"), Some("bytes 0-5/10"));
+}
+
+#[test]
+fn unsatisfiable_range() {
+    let base = fixture("unsatisfiable-range");
+    fs::write(base.join("public/numbers.txt"), "0123456789").unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=10-20"]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::RangeNotSatisfiable);
+    assert_eq!(response.get_header("Content-Range"), Some("bytes */10"));
+}
+
+#[test]
+fn if_range() {
+    let base = fixture("if-range");
+    fs::write(base.join("public/numbers.txt"), "0123456789").unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let modified = fs::metadata(base.join("public/numbers.txt")).unwrap().modified().unwrap();
+    let current = format!("If-Range: {}", format_http_date(modified));
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=0-1", &current]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::PartialContent);
+
+    let outdated = format!("If-Range: {}", format_http_date(modified - Duration::from_secs(60)));
+    let response = send(&handler, "GET", "/numbers.txt", &["Range: bytes=0-1", &outdated]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+}
+
+#[test]
+fn conditional_requests() {
+    let base = fixture("conditional-requests");
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = fetch(&handler, "/docs/guide.txt");
+    let etag = response.get_header("ETag").unwrap().to_string();
+    let last_modified = response.get_header("Last-Modified").unwrap().to_string();
+    assert!(etag.starts_with('"') && etag.ends_with('"'));
+
+    for header in [format!("If-None-Match: \"other\", {}", etag), format!("If-None-Match: W/{}", etag), String::from("If-None-Match: *")] {
+        let response = send(&handler, "GET", "/docs/guide.txt", &[&header]);
+        assert_eq!(*response.get_status(), HTTPStatusCode::NotModified, "{}", header);
+        assert_eq!(response.get_header("ETag"), Some(etag.as_str()));
+        assert!(!response.has_body());
+    }
+
+    let response = send(&handler, "GET", "/docs/guide.txt", &[&format!("If-Modified-Since: {}", last_modified)]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotModified);
+
+    // If-None-Match takes precedence over If-Modified-Since
+    let response = send(&handler, "GET", "/docs/guide.txt", &["If-None-Match: \"other\"", &format!("If-Modified-Since: {}", last_modified)]);
+    assert_eq!(sent_text(response), "Guide");
+
+    let response = send(&handler, "GET", "/docs/guide.txt", &["If-Match: \"other\""]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::PreconditionFailed);
+    let response = send(&handler, "GET", "/docs/guide.txt", &[&format!("If-Match: W/{}", etag)]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::PreconditionFailed);
+    let response = send(&handler, "GET", "/docs/guide.txt", &[&format!("If-Match: {}", etag)]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+
+    let modified = fs::metadata(base.join("public/docs/guide.txt")).unwrap().modified().unwrap();
+    let earlier = format!("If-Unmodified-Since: {}", format_http_date(modified - Duration::from_secs(60)));
+    assert_eq!(*send(&handler, "GET", "/docs/guide.txt", &[&earlier]).get_status(), HTTPStatusCode::PreconditionFailed);
+    let current = format!("If-Unmodified-Since: {}", last_modified);
+    assert_eq!(*send(&handler, "GET", "/docs/guide.txt", &[&current]).get_status(), HTTPStatusCode::OK);
+
+    // Entity tags in If-Range are compared strongly
+    let response = send(&handler, "GET", "/docs/guide.txt", &["Range: bytes=0-1", &format!("If-Range: {}", etag)]);
+    assert_eq!(sent_text(response), "Gu");
+    let response = send(&handler, "GET", "/docs/guide.txt", &["Range: bytes=0-1", &format!("If-Range: W/{}", etag)]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+}
+
+#[test]
+fn precompressed_files() {
+    let base = fixture("precompressed-files");
+    fs::write(base.join("public/app.js"), "plain").unwrap();
+    fs::write(base.join("public/app.js.gz"), "gzipped").unwrap();
+    fs::write(base.join("public/app.js.br"), "brotli").unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+
+    let response = send(&handler, "GET", "/app.js", &["Accept-Encoding: gzip, deflate, br"]);
+    assert_eq!(response.get_header("Content-Encoding"), Some("br"));
+    assert_eq!(response.get_header("Content-Type"), Some("text/javascript; charset=utf-8"));
+    assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(sent_text(response), "brotli");
+
+    let response = send(&handler, "GET", "/app.js", &["Accept-Encoding: gzip, br;q=0.5"]);
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(sent_text(response), "gzipped");
+
+    let response = fetch(&handler, "/app.js");
+    assert_eq!(response.get_header("Content-Encoding"), None);
+    assert_eq!(response.get_header("Vary"), Some("Accept-Encoding"));
+    assert_eq!(sent_text(response), "plain");
+
+    // Files without precompressed variants are not affected
+    let response = send(&handler, "GET", "/docs/guide.txt", &["Accept-Encoding: gzip, br"]);
+    assert_eq!(response.get_header("Content-Encoding"), None);
+    assert_eq!(response.get_header("Vary"), None);
+    assert_eq!(sent_text(response), "Guide");
+}
+
+#[test]
+fn svgz_files() {
+    let base = fixture("svgz-files");
+    let image = [0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 0xFF, 1, 2, 3];
+    fs::write(base.join("public/logo.svgz"), image).unwrap();
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+    handler.set_compression(Compression::new().with_min_size(0));
+
+    // The file already is gzip data, it is neither sent as plain svg nor compressed again
+    let response = send(&handler, "GET", "/logo.svgz", &["Accept-Encoding: gzip"]);
+    assert_eq!(response.get_header("Content-Type"), Some("image/svg+xml"));
+    assert_eq!(response.get_header("Content-Encoding"), Some("gzip"));
+    assert_eq!(sent_body(response), image);
+}
+
+#[test]
+fn mounted_folders() {
+    let base = fixture("mounted-folders");
+    fs::create_dir_all(base.join("uploads/reports")).unwrap();
+    fs::write(base.join("uploads/reports/q1.txt"), "Q1").unwrap();
+    fs::write(base.join("uploads/index.html"), "<h1>Uploads</h1>").unwrap();
+
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(base.join("public"));
+    handler.mount_public_folder("/assets", PublicFolder::new(base.join("public/docs")).with_cache_control("max-age=3600"));
+    handler.mount_public_folder("/uploads/", PublicFolder::new(base.join("uploads")).with_index_file(None).with_listing(true));
+    handler.set_listener("GET", "/assets/live.txt", |_: HTTPRequest| "Listener");
+
+    let response = fetch(&handler, "/assets/guide.txt");
+    assert_eq!(response.get_header("Cache-Control"), Some("max-age=3600"));
+    assert_eq!(sent_text(response), "Guide");
+    assert_eq!(fetch(&handler, "/docs/guide.txt").get_header("Cache-Control"), None);
+
+    // Listeners win over files, prefixes only match whole segments
+    assert_eq!(fetch(&handler, "/assets/live.txt").get_body_str(), Some("Listener"));
+    assert_eq!(*fetch(&handler, "/assetsguide.txt").get_status(), HTTPStatusCode::NotFound);
+
+    // Files missing in a mount fall through to folders with shorter prefixes
+    fs::create_dir(base.join("public/assets")).unwrap();
+    fs::write(base.join("public/assets/logo.svg"), "<svg/>").unwrap();
+    assert_eq!(sent_text(fetch(&handler, "/assets/logo.svg")), "<svg/>");
+
+    // Every mount has its own index file and listing settings
+    let response = fetch(&handler, "/uploads");
+    assert_eq!(response.get_header("Location"), Some("/uploads/"));
+    let response = fetch(&handler, "/uploads/");
+    let listing = response.get_body_str().unwrap();
+    assert!(listing.contains("<title>Index of /uploads/</title>"));
+    assert!(listing.contains("<a href=\"reports/\">reports/</a>"));
+    assert!(!listing.contains("Uploads</h1>"));
+    assert_eq!(sent_text(fetch(&handler, "/uploads/reports/q1.txt")), "Q1");
+    assert!(!sent_text(fetch(&handler, "/uploads/../../secret.txt")).contains("Secret"));
+
+    // Mounting at the same prefix replaces the folder
+    handler.mount_public_folder("/assets", base.join("uploads"));
+    assert_eq!(sent_text(fetch(&handler, "/assets/reports/q1.txt")), "Q1");
+}
+
+#[test]
+#[should_panic]
+fn invalid_mount_prefix() {
+    RequestHandler::new().mount_public_folder("assets", "./public");
+}
+
+#[test]
+fn spa_fallback() {
+    let base = fixture("spa-fallback");
+    let mut handler = RequestHandler::new();
+    handler.set_public_folder(PublicFolder::new(base.join("public")).with_spa_fallback(Some("index.html")).with_spa_exclude("/api/"));
+    handler.set_listener("GET", "/api/users", |_: HTTPRequest| "Users");
+
+    let html = "Accept: text/html,application/xhtml+xml,*/*;q=0.8";
+    let response = send(&handler, "GET", "/settings/profile", &[html]);
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(response.get_header("Content-Type"), Some("text/html; charset=utf-8"));
+    assert_eq!(sent_text(response), "<h1>Home</h1>");
+
+    // Existing files and listeners are served as usual
+    assert_eq!(sent_text(send(&handler, "GET", "/docs/guide.txt", &[html])), "Guide");
+    assert_eq!(send(&handler, "GET", "/api/users", &[html]).get_body_str(), Some("Users"));
+
+    // Requests that do not want the app get a real 404
+    for (uri, headers) in [
+        ("/settings", vec!["Accept: application/json"]),
+        ("/settings", vec!["Accept: text/html;q=0, */*"]),
+        ("/settings", vec![]),
+        ("/missing.js", vec![html]),
+        ("/api/unknown", vec![html]),
+        ("/api", vec![html]),
+    ] {
+        assert_eq!(*send(&handler, "GET", uri, &headers).get_status(), HTTPStatusCode::NotFound, "{} {:?}", uri, headers);
+    }
+    let request = HTTPRequest::parse(vec!["POST /settings HTTP/1.1", html]).unwrap();
+    assert_eq!(*handler.match_request(request).get_status(), HTTPStatusCode::NotFound);
+
+    // Files of other mounts take precedence over the fallback
+    handler.mount_public_folder("/static", base.join("public/docs"));
+    assert_eq!(sent_text(send(&handler, "GET", "/static/guide.txt", &[html])), "Guide");
+    assert_eq!(sent_text(send(&handler, "GET", "/static/unknown", &[html])), "<h1>Home</h1>");
+}
@@ -0,0 +1,61 @@
+use carola::router::{RouteError, RouteTree};
+
+#[test]
+fn static_routes() {
+    let mut tree = RouteTree::new();
+    tree.insert("/", 0).unwrap();
+    tree.insert("/users", 1).unwrap();
+    tree.insert("/users/all", 2).unwrap();
+    tree.insert("/uploads", 3).unwrap();
+
+    assert_eq!(*tree.at("/").unwrap().value, 0);
+    assert_eq!(*tree.at("/users").unwrap().value, 1);
+    assert_eq!(*tree.at("/users/all").unwrap().value, 2);
+    assert_eq!(*tree.at("/uploads").unwrap().value, 3);
+    assert!(tree.at("/user").is_none());
+    assert!(tree.at("/users/").is_none());
+}
+
+#[test]
+fn priority() {
+    let mut tree = RouteTree::new();
+    tree.insert("/files/*path", "catch-all").unwrap();
+    tree.insert("/files/:name", "param").unwrap();
+    tree.insert("/files/index", "static").unwrap();
+
+    assert_eq!(*tree.at("/files/index").unwrap().value, "static");
+
+    let matched = tree.at("/files/readme").unwrap();
+    assert_eq!(*matched.value, "param");
+    assert_eq!(matched.params["name"], "readme");
+
+    let matched = tree.at("/files/docs/readme").unwrap();
+    assert_eq!(*matched.value, "catch-all");
+    assert_eq!(matched.params["path"], "docs/readme");
+}
+
+#[test]
+fn backtracking() {
+    let mut tree = RouteTree::new();
+    tree.insert("/users/me/settings", "settings").unwrap();
+    tree.insert("/users/:id/posts", "posts").unwrap();
+
+    let matched = tree.at("/users/me/posts").unwrap();
+    assert_eq!(*matched.value, "posts");
+    assert_eq!(matched.params["id"], "me");
+}
+
+#[test]
+fn conflicts() {
+    let mut tree = RouteTree::new();
+    tree.insert("/users/:id", ()).unwrap();
+
+    assert_eq!(
+        tree.insert("/users/:id", ()),
+        Err(RouteError::Conflict { pattern: "/users/:id".to_string(), existing: "/users/:id".to_string() })
+    );
+    assert!(matches!(tree.insert("/users/:name/posts", ()), Err(RouteError::Conflict { .. })));
+    assert!(matches!(tree.insert("/files/*rest/more", ()), Err(RouteError::InvalidPattern { .. })));
+    assert!(matches!(tree.insert("users", ()), Err(RouteError::InvalidPattern { .. })));
+    assert_eq!(tree.len(), 1);
+}
@@ -0,0 +1,3 @@
+mod route_tree;
+mod mounting;
+mod support;
\ No newline at end of file
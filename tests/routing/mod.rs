@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    sync::{atomic::{AtomicUsize, Ordering}, Arc},
+    thread,
+};
+
+use carola::{
+    handler::RequestHandler,
+    http::{HTTPRequest, HTTPResponse, HTTPStatusCode},
+};
+
+use crate::support::{body, get, request};
+
+#[test]
+fn path_parameters() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/users/:id/posts/:post", |request: HTTPRequest| {
+        let body = format!("{}:{}", request.param("id").unwrap(), request.param("post").unwrap());
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(body.into()))
+    });
+
+    let response = handler.match_request(get("/users/42/posts/7"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(body(&response), "42:7");
+
+    let response = handler.match_request(get("/users/42"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn wildcard_segments() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/static/*rest", |request: HTTPRequest| {
+        let body = request.param("rest").unwrap().to_string();
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(body.into()))
+    });
+
+    let response = handler.match_request(get("/static/css/main.css"));
+    assert_eq!(body(&response), "css/main.css");
+}
+
+#[test]
+fn encoded_segments() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/users/:id", |request: HTTPRequest| request.param("id").unwrap().to_string());
+    handler.set_listener("GET", "/search results", |_: HTTPRequest| "search");
+
+    // An encoded slash stays inside of its segment, and is decoded with it
+    assert_eq!(body(&handler.match_request(get("/users/a%2Fb"))), "a/b");
+    assert_eq!(body(&handler.match_request(get("/users/100%25"))), "100%");
+    assert_eq!(body(&handler.match_request(get("/users/caf%C3%A9"))), "caf\u{e9}");
+    assert_eq!(*handler.match_request(get("/users/a/b")).get_status(), HTTPStatusCode::NotFound);
+    assert_eq!(body(&handler.match_request(get("/search%20results"))), "search");
+}
+
+#[test]
+fn exact_paths_take_precedence() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/users/:id", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("param").into()))
+    });
+    handler.set_listener("GET", "/users/me", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("exact").into()))
+    });
+
+    assert_eq!(body(&handler.match_request(get("/users/me"))), "exact");
+    assert_eq!(body(&handler.match_request(get("/users/you"))), "param");
+}
+
+#[test]
+fn query_string_is_not_part_of_the_path() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |request: HTTPRequest| {
+        let body = request.query("x").unwrap_or_default().to_string();
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(body.into()))
+    });
+
+    let response = handler.match_request(get("/example?x=1"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(body(&response), "1");
+}
+
+#[test]
+fn method_not_allowed() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("POST", "/users/:id", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), None)
+    });
+    handler.set_listener("DELETE", "/users/:id", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), None)
+    });
+
+    let response = handler.match_request(get("/users/42"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::MethodNotAllowed);
+    assert_eq!(response.get_headers()["Allow"], "POST, DELETE, OPTIONS");
+
+    let response = handler.match_request(get("/posts/42"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn method_not_allowed_callback() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("POST", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), None)
+    });
+    handler.set_method_not_allowed_callback(|_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::MethodNotAllowed, HashMap::new(), Some(String::from("nope").into()))
+    });
+
+    let response = handler.match_request(get("/example"));
+    assert_eq!(body(&response), "nope");
+    assert_eq!(response.get_headers()["Allow"], "POST, OPTIONS");
+}
+
+#[test]
+fn automatic_head() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("Hello World!").into()))
+    });
+
+    let response = handler.match_request(request("HEAD", "/example"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::OK);
+    assert_eq!(response.get_headers()["Content-Length"], "12");
+    assert!(response.get_body().is_none());
+    assert_eq!(response.construct(), b"HTTP/1.1 200 OK\r\nContent-Length:12\r\n\r\n");
+}
+
+#[test]
+fn automatic_options() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), None)
+    });
+    handler.set_listener("PUT", "/other", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), None)
+    });
+
+    let response = handler.match_request(request("OPTIONS", "/example"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::NoContent);
+    assert_eq!(response.get_headers()["Allow"], "GET, HEAD, OPTIONS");
+
+    let response = handler.match_request(request("OPTIONS", "*"));
+    assert_eq!(response.get_headers()["Allow"], "GET, HEAD, PUT, OPTIONS");
+
+    let response = handler.match_request(request("OPTIONS", "/missing"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::NotFound);
+}
+
+#[test]
+fn explicit_head_and_options_listeners() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("get").into()))
+    });
+    handler.set_listener("HEAD", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::Accepted, HashMap::new(), Some(String::from("head").into()))
+    });
+    handler.set_listener("OPTIONS", "/example", |_: HTTPRequest| {
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(String::from("options").into()))
+    });
+
+    let response = handler.match_request(request("HEAD", "/example"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::Accepted);
+    // The body of an explicit listener is not sent either, only its length
+    let raw = String::from_utf8(response.construct()).unwrap();
+    assert!(raw.contains("Content-Length:4\r\n"), "{}", raw);
+    assert!(raw.ends_with("\r\n\r\n"), "{}", raw);
+    assert_eq!(body(&handler.match_request(request("OPTIONS", "/example"))), "options");
+}
+
+#[test]
+fn shared_state() {
+    let mut handler = RequestHandler::new();
+    handler.set_state(AtomicUsize::new(0));
+    handler.set_listener("GET", "/visits", |request: HTTPRequest| {
+        let visits = request.state::<AtomicUsize>().unwrap().fetch_add(1, Ordering::SeqCst) + 1;
+        HTTPResponse::new("1.1", HTTPStatusCode::OK, HashMap::new(), Some(visits.to_string().into()))
+    });
+
+    let handler = Arc::new(handler);
+    let threads = (0..8)
+        .map(|_| {
+            let handler = handler.clone();
+            thread::spawn(move || handler.match_request(get("/visits")))
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        assert_eq!(*thread.join().unwrap().get_status(), HTTPStatusCode::OK);
+    }
+
+    assert_eq!(body(&handler.match_request(get("/visits"))), "9");
+}
+
+#[test]
+fn response_version_from_request() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/example", |_: HTTPRequest| HTTPResponse::text("Hello World!"));
+    handler.set_listener("GET", "/pinned", |_: HTTPRequest| HTTPResponse::builder().version("1.1").build());
+
+    let request = HTTPRequest::parse(vec!["GET /example HTTP/1.0"]).unwrap();
+    assert_eq!(handler.match_request(request).get_version(), "1.0");
+    assert_eq!(handler.match_request(get("/example")).get_version(), "1.1");
+
+    let request = HTTPRequest::parse(vec!["GET /pinned HTTP/1.0"]).unwrap();
+    assert_eq!(handler.match_request(request).get_version(), "1.1");
+}
+
+#[test]
+fn listeners_return_into_response() {
+    let mut handler = RequestHandler::new();
+    handler.set_listener("GET", "/text", |_: HTTPRequest| "Hello World!");
+    handler.set_listener("POST", "/users", |_: HTTPRequest| {
+        (HTTPStatusCode::Created, [("Location", "/users/1")], String::from("Created"))
+    });
+    handler.set_listener("GET", "/users/:id", |request: HTTPRequest| {
+        let id = request.param("id")?.parse::<u32>().ok()?;
+        Some(format!("User {}", id))
+    });
+    handler.set_listener("GET", "/divide/:n", |request: HTTPRequest| {
+        match request.param("n").unwrap().parse::<u32>() {
+            Ok(0) => Err((HTTPStatusCode::BadRequest, "Division by zero")),
+            Ok(n) => Ok((100 / n).to_string()),
+            Err(_) => Err((HTTPStatusCode::BadRequest, "Not a number")),
+        }
+    });
+
+    let response = handler.match_request(get("/text"));
+    assert_eq!(body(&response), "Hello World!");
+    assert_eq!(response.get_header("Content-Type"), Some("text/plain; charset=utf-8"));
+
+    let response = handler.match_request(request("POST", "/users"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::Created);
+    assert_eq!(response.get_header("Location"), Some("/users/1"));
+    assert_eq!(body(&response), "Created");
+
+    assert_eq!(body(&handler.match_request(get("/users/42"))), "User 42");
+    assert_eq!(*handler.match_request(get("/users/abc")).get_status(), HTTPStatusCode::NotFound);
+
+    assert_eq!(body(&handler.match_request(get("/divide/4"))), "25");
+    let response = handler.match_request(get("/divide/0"));
+    assert_eq!(*response.get_status(), HTTPStatusCode::BadRequest);
+    assert_eq!(body(&response), "Division by zero");
+}
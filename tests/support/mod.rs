@@ -0,0 +1,64 @@
+#![allow(dead_code)]
+
+use carola::{
+    handler::RequestHandler,
+    http::{HTTPRequest, HTTPResponse},
+};
+
+/// Parses a request with the given method and target, coming from `localhost:8000`
+pub fn request(method: &str, uri: &str) -> HTTPRequest {
+    request_with(method, uri, &[])
+}
+
+/// Same as `request`, with additional header lines
+pub fn request_with(method: &str, uri: &str, headers: &[&str]) -> HTTPRequest {
+    let request_line = format!("{} {} HTTP/1.1", method, uri);
+    let lines = [&[request_line.as_str(), "Host: localhost:8000"], headers].concat();
+    HTTPRequest::parse(lines).expect("Failed to parse")
+}
+
+pub fn get(uri: &str) -> HTTPRequest {
+    request("GET", uri)
+}
+
+/// Runs a `GET` request through the handler
+pub fn fetch(handler: &RequestHandler, uri: &str) -> HTTPResponse {
+    send(handler, "GET", uri, &[])
+}
+
+/// Runs a request with the given header lines through the handler
+pub fn send(handler: &RequestHandler, method: &str, uri: &str, headers: &[&str]) -> HTTPResponse {
+    handler.match_request(request_with(method, uri, headers))
+}
+
+/// Returns the buffered body of a response as text
+pub fn body(response: &HTTPResponse) -> &str {
+    response.get_body_str().unwrap_or_default()
+}
+
+/// Returns the body as it is sent, buffered or streamed, joining the chunks of a chunked body
+pub fn sent_body(response: HTTPResponse) -> Vec<u8> {
+    let response = response.construct();
+    let start = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap() + 4;
+    let head = String::from_utf8_lossy(&response[..start]).to_string();
+    let mut rest = &response[start..];
+    if !head.contains("Transfer-Encoding:chunked") {
+        return rest.to_vec();
+    }
+
+    let mut body = Vec::new();
+    loop {
+        let line = rest.windows(2).position(|window| window == b"\r\n").unwrap();
+        let size = usize::from_str_radix(std::str::from_utf8(&rest[..line]).unwrap(), 16).unwrap();
+        if size == 0 {
+            return body;
+        }
+        body.extend_from_slice(&rest[line + 2..line + 2 + size]);
+        rest = &rest[line + 2 + size + 2..];
+    }
+}
+
+/// Same as `sent_body`, as text
+pub fn sent_text(response: HTTPResponse) -> String {
+    String::from_utf8(sent_body(response)).unwrap()
+}